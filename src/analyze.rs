@@ -1,244 +1,589 @@
 // rustc crates
 use rustc_ast::ast::LitKind;
-use rustc_hir::Lit;
-use rustc_middle::mir::{BinOp, UnOp};
+use rustc_hir::def::{CtorOf, DefKind};
+use rustc_hir::{BindingMode, ByRef, Lit, Mutability};
+use rustc_middle::mir::{BinOp, BorrowKind, UnOp};
 use rustc_middle::thir::LocalVarId;
 use rustc_middle::thir::LogicalOp;
-use rustc_middle::ty::TyCtxt;
+use rustc_middle::ty::{self, GenericArgsRef, TyCtxt, UpvarCapture};
 use rustc_middle::ty::{Ty, TyKind};
 use rustc_span::def_id::LocalDefId;
+use rustc_target::abi::FIRST_VARIANT;
 
 // std crates
 use std::boxed::Box;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap as Map;
-use std::iter::Peekable;
+use std::collections::HashSet as Set;
 use std::rc::Rc;
 
 // Own crates
+use crate::run::{DEFAULT_UNFOLD, NON_STRICT, UNFOLD, UNROLL};
 use crate::thir::rthir::*;
 mod core;
 mod env;
 mod gen_cstr;
 mod helper_struct;
+mod infer;
 mod lir;
+mod model;
+mod recursion;
+mod sort;
 mod special;
 mod sub;
+mod symbols;
 mod util;
-pub use {env::Env, helper_struct::*, lir::*};
+pub use {env::Env, helper_struct::*, lir::*, sort::*, symbols::Symbols};
 
 pub fn analyze<'tcx>(
     main_id: LocalDefId, fn_map: Map<LocalDefId, Rc<RThir<'tcx>>>, tcx: TyCtxt<'tcx>,
-) -> Result<(), AnalysisError> {
+) -> Result<Verdict, AnalysisError> {
     Analyzer::run(main_id, fn_map, tcx)
 }
 
 struct Analyzer<'tcx> {
     fn_map: Map<LocalDefId, Rc<RThir<'tcx>>>,
+    /// Generic fns reduced with the generic args of a call
+    mono_map: RefCell<Map<(LocalDefId, GenericArgsRef<'tcx>), Rc<RThir<'tcx>>>>,
     tcx: TyCtxt<'tcx>,
+    /// Calls of each fn without a contract currently being inlined
+    unfolding: RefCell<Map<LocalDefId, usize>>,
+    /// Fns whose contract has been verified, per instantiation
+    verified: RefCell<Set<(LocalDefId, GenericArgsRef<'tcx>)>>,
+    unrolled: Cell<bool>,
+    unfolded: Cell<bool>,
+    inferring: Cell<bool>,
+    /// Fresh symbols made so far, numbering them
+    fresh: Cell<usize>,
+    /// Versions of the variables, shared by every env so no two definitions share a symbol
+    symbols: Symbols<'tcx>,
 }
 
 impl<'tcx> Analyzer<'tcx> {
     pub fn new(fn_map: Map<LocalDefId, Rc<RThir<'tcx>>>, tcx: TyCtxt<'tcx>) -> Self {
-        Self { fn_map, tcx }
+        Self {
+            fn_map,
+            mono_map: RefCell::new(Map::new()),
+            tcx,
+            unfolding: RefCell::new(Map::new()),
+            verified: RefCell::new(Set::new()),
+            unrolled: Cell::new(false),
+            unfolded: Cell::new(false),
+            inferring: Cell::new(false),
+            fresh: Cell::new(0),
+            symbols: Symbols::new(tcx),
+        }
     }
 
     pub fn run(
         main_id: LocalDefId, fn_map: Map<LocalDefId, Rc<RThir<'tcx>>>, tcx: TyCtxt<'tcx>,
-    ) -> Result<(), AnalysisError> {
-        let mut analyzer = Analyzer::new(fn_map, tcx);
+    ) -> Result<Verdict, AnalysisError> {
+        let analyzer = Analyzer::new(fn_map, tcx);
         let main = analyzer.get_fn(main_id)?;
-        analyzer.analyze_main(main)
+        analyzer.analyze_main(main)?;
+        if analyzer.is_bounded() {
+            Ok(Verdict::Bounded {
+                unrolled: analyzer.unrolled.get(),
+                unfolded: analyzer.unfolded.get(),
+            })
+        } else {
+            Ok(Verdict::Proved)
+        }
     }
 
     /// Main analysis functions
     /// - analyze_loop
 
     fn analyze_loop(
-        &self, invariant: Rc<RExpr<'tcx>>,
-        stmts_iter: &mut Peekable<impl Iterator<Item = Rc<RExpr<'tcx>>>>, env: &mut Env<'tcx>,
+        &self, invariants: Vec<Rc<RExpr<'tcx>>>, expr: Rc<RExpr<'tcx>>, rest: &[Rc<RExpr<'tcx>>],
+        env: &mut Env<'tcx>,
     ) -> Result<(), AnalysisError> {
-        // verify before loop
-        let mut invariants = vec![invariant.clone()];
-        let mut before_loop_env = env.gen_new_env("loop".to_string(), invariant.clone())?;
-        self.verify_before_loop(
-            invariant.clone(),
-            &mut invariants,
-            stmts_iter,
-            &mut before_loop_env,
-        )?;
-
-        let expr = stmts_iter.next().unwrap();
-        if let RExprKind::Loop { body } = expr.kind.clone() {
-            let mut loop_env =
-                before_loop_env.gen_new_env("inner_loop".to_string(), expr.clone())?;
-            self.verify_inner_loop(body.clone(), invariants, &mut loop_env)?;
-        } else {
-            return Err(AnalysisError::UnsupportedPattern(
-                "Multiple invariant is not suppoerted".into(),
-            ));
+        let body = match &expr.kind {
+            RExprKind::Loop { body } => body.clone(),
+            _ => return Err(AnalysisError::UnsupportedPattern("Unknown loop pattern".into())),
+        };
+        let (head_invariants, body) = self.split_body_invariants(body);
+        let mut invariants = [invariants, head_invariants].concat();
+        if invariants.is_empty() {
+            if let Some(bound) = UNROLL.get() {
+                return self.analyze_unrolled_loop(*bound, body, expr, env);
+            }
+            invariants = self.infer_invariants(body.clone(), expr.clone(), rest, env)?;
+        }
+
+        self.verify_before_loop(&invariants, env)?;
+        self.set_var_map(body.clone(), env)?;
+        for invariant in invariants.iter() {
+            let constraint = self.expr_to_constraint(invariant.clone(), env)?;
+            env.add_assumption(constraint, invariant.clone());
         }
+
+        let mut loop_env = env.gen_new_env("loop".to_string(), expr.clone())?;
+        self.verify_inner_loop(body, &invariants, &mut loop_env)?;
+        self.merge_loop_env(loop_env, expr, env);
         Ok(())
     }
 
     fn verify_before_loop(
-        &self, invariant: Rc<RExpr<'tcx>>, invariants: &mut Vec<Rc<RExpr<'tcx>>>,
-        stmts_iter: &mut Peekable<impl Iterator<Item = Rc<RExpr<'tcx>>>>, env: &mut Env<'tcx>,
+        &self, invariants: &[Rc<RExpr<'tcx>>], env: &mut Env<'tcx>,
+    ) -> Result<(), AnalysisError> {
+        for invariant in invariants.iter() {
+            let constraint = self.expr_to_constraint(invariant.clone(), env)?;
+            env.add_assumption(constraint, invariant.clone());
+            let assumptions = env.get_assumptions_for_verify()?;
+            self.verify(assumptions, env)?;
+        }
+        Ok(())
+    }
+
+    /// Bounded model checking: loops without invariants are unrolled `bound` times
+    fn analyze_unrolled_loop(
+        &self, bound: usize, body: Rc<RExpr<'tcx>>, expr: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
     ) -> Result<(), AnalysisError> {
-        let constraint = self.expr_to_constraint(invariant.clone(), env)?;
-        env.add_assumption(constraint.clone(), invariant.clone());
-        let assumptions = env.get_assumptions_for_verify()?;
-        self.verify(assumptions, env)?;
-        while let Some(inv) = stmts_iter.next_if(|stmt| self.is_invariant(stmt.clone())) {
-            if let RExprKind::Call { args, .. } = &inv.kind {
-                invariants.push(args[0].clone());
-                let constraint = self.expr_to_constraint(args[0].clone(), env)?;
-                env.add_assumption(constraint, inv);
-                let assumptions = env.get_assumptions_for_verify()?;
-                self.verify(assumptions, env)?;
+        let mut loop_env = env.gen_new_env("loop".to_string(), expr.clone())?;
+        self.unroll_loop(bound, body, expr.clone(), &mut loop_env)?;
+        self.merge_loop_env(loop_env, expr, env);
+        Ok(())
+    }
+
+    fn unroll_loop(
+        &self, bound: usize, body: Rc<RExpr<'tcx>>, expr: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
+    ) -> Result<(), AnalysisError> {
+        if bound == 0 {
+            // Unwinding assertion: no path may still be in the loop
+            env.add_assumption("false".to_string(), expr.clone());
+            if NON_STRICT.get().is_some() {
+                self.unrolled.set(true);
+            } else {
+                let smt = env.get_assumptions_for_verify()?;
+                self.verify(smt, env)
+                    .map_err(|_| AnalysisError::UnwindingError { span: expr.span })?;
             }
+            return Ok(());
         }
+
+        self.analyze_block(body.clone(), env)?;
+        env.resume_continues(expr.clone(), &self.symbols);
+        if env.is_unreachable() {
+            return Ok(());
+        }
+        let mut next_env = env.gen_new_env("loop".to_string(), expr.clone())?;
+        self.unroll_loop(bound - 1, body, expr, &mut next_env)?;
+        env.merge_iteration_env(next_env);
         Ok(())
     }
 
-    fn is_invariant(&self, expr: Rc<RExpr<'tcx>>) -> bool {
+    /// Invariants may also be written as the first statements of the loop body.
+    /// For `while`, the body is the then block of the guard.
+    fn split_body_invariants(
+        &self, body: Rc<RExpr<'tcx>>,
+    ) -> (Vec<Rc<RExpr<'tcx>>>, Rc<RExpr<'tcx>>) {
+        if let RExprKind::Block { stmts, expr: Some(expr) } = &body.kind {
+            if let RExprKind::If { cond, then, else_opt: Some(else_block) } = &expr.kind {
+                if stmts.is_empty() && Analyzer::is_break_block(else_block) {
+                    let (invariants, then) = self.split_block_invariants(then.clone());
+                    let kind = RExprKind::If {
+                        cond: cond.clone(),
+                        then,
+                        else_opt: Some(else_block.clone()),
+                    };
+                    let expr = Rc::new(RExpr::new(kind, expr.ty, expr.span));
+                    let kind = RExprKind::Block { stmts: Vec::new(), expr: Some(expr) };
+                    return (invariants, Rc::new(RExpr::new(kind, body.ty, body.span)));
+                }
+            }
+        }
+        self.split_block_invariants(body)
+    }
+
+    fn split_block_invariants(
+        &self, block: Rc<RExpr<'tcx>>,
+    ) -> (Vec<Rc<RExpr<'tcx>>>, Rc<RExpr<'tcx>>) {
+        if let RExprKind::Block { stmts, expr } = &block.kind {
+            let count = stmts.iter().take_while(|stmt| self.is_invariant(stmt)).count();
+            if count > 0 {
+                let invariants = stmts[..count]
+                    .iter()
+                    .map(|stmt| match &stmt.kind {
+                        RExprKind::Call { args, .. } => args[0].clone(),
+                        _ => unreachable!(),
+                    })
+                    .collect();
+                let kind = RExprKind::Block { stmts: stmts[count..].to_vec(), expr: expr.clone() };
+                return (invariants, Rc::new(RExpr::new(kind, block.ty, block.span)));
+            }
+        }
+        (Vec::new(), block)
+    }
+
+    fn is_break_block(block: &Rc<RExpr<'tcx>>) -> bool {
+        match &block.kind {
+            RExprKind::Block { stmts, expr: None } => {
+                matches!(stmts.as_slice(), [stmt] if matches!(stmt.kind, RExprKind::Break { .. }))
+            }
+            _ => false,
+        }
+    }
+
+    fn is_invariant(&self, expr: &Rc<RExpr<'tcx>>) -> bool {
+        self.is_t3modules_call(expr, "invariant")
+    }
+
+    fn is_t3modules_call(&self, expr: &Rc<RExpr<'tcx>>, name: &str) -> bool {
         match &expr.kind {
             RExprKind::Call { ty, .. } => match ty.kind() {
                 TyKind::FnDef(def_id, ..) => {
                     let fn_info = self.get_fn_info(def_id);
-                    match fn_info[1].as_str() {
-                        "invariant" => true,
-                        _ => false,
-                    }
+                    fn_info[0] == "t3modules" && fn_info.get(1).is_some_and(|f| f == name)
                 }
-                _ => panic!("Call has not have FnDef"),
+                _ => false,
             },
             _ => false,
         }
     }
 
     fn verify_inner_loop(
-        &self, block: Rc<RExpr<'tcx>>, invariants: Vec<Rc<RExpr<'tcx>>>, env: &mut Env<'tcx>,
+        &self, block: Rc<RExpr<'tcx>>, invariants: &[Rc<RExpr<'tcx>>], env: &mut Env<'tcx>,
     ) -> Result<(), AnalysisError> {
-        self.set_var_map(block.clone(), invariants, env);
         self.analyze_block(block, env)?;
-        let smt = env.get_assumptions_for_verify()?;
-        println!("{}", smt);
-        self.verify(smt, env)
-    }
-
-    fn set_var_map(
-        &self, block: Rc<RExpr<'tcx>>, invariants: Vec<Rc<RExpr<'tcx>>>, env: &mut Env<'tcx>,
-    ) {
-        let inv_varv = Analyzer::search_inv(invariants);
-        let varv = Analyzer::search_used_var(block.clone());
-        println!("{:?}", varv);
-        let refresh_varv = varv.iter().filter(|var| !inv_varv.contains(var));
-        for var in refresh_varv {
-            let (current_name, ty) = env.var_map.get(var).unwrap().clone();
-            let new_name = format!("{}_{}", env.name, current_name);
-            env.add_parameter(new_name.clone(), &ty, var, block.clone());
-            env.var_map.insert(*var, (new_name, ty.clone()));
+        for continue_exit in Analyzer::take_continues(env) {
+            for invariant in invariants.iter() {
+                let inv_env = self.gen_preservation_env(invariant.clone(), &continue_exit, env)?;
+                let smt = inv_env.get_assumptions_for_verify()?;
+                self.verify(smt, &inv_env)?;
+            }
         }
+        Ok(())
     }
 
-    fn search_inv(invariants: Vec<Rc<RExpr<'tcx>>>) -> Vec<LocalVarId> {
-        let mut varv: Vec<LocalVarId> = Vec::new();
-
-        for invariant in invariants {
-            Analyzer::search_var(invariant, &mut varv);
+    /// Every path going back to the loop head must re-establish the invariants
+    fn take_continues(env: &mut Env<'tcx>) -> Vec<LoopExit<'tcx>> {
+        let mut continues = env.take_exits(ExitKind::Continue);
+        if !env.is_unreachable() {
+            continues.push(LoopExit {
+                kind: ExitKind::Continue,
+                cond: env.alive().unwrap_or_else(|| "true".to_string()),
+                var_map: env.var_map.clone(),
+                value: None,
+            });
         }
-        varv
+        continues
     }
 
-    fn search_var(expr: Rc<RExpr<'tcx>>, varv: &mut Vec<LocalVarId>) {
-        use RExprKind::*;
+    fn gen_preservation_env(
+        &self, invariant: Rc<RExpr<'tcx>>, continue_exit: &LoopExit<'tcx>, env: &Env<'tcx>,
+    ) -> Result<Env<'tcx>, AnalysisError> {
+        let mut inv_env = env.clone();
+        inv_env.var_map = continue_exit.var_map.clone();
+        let constraint = self.expr_to_constraint(invariant.clone(), &mut inv_env)?;
+        inv_env.add_lir(Lir::new_assume(
+            format!("(=> {} {})", continue_exit.cond, constraint),
+            invariant,
+        ));
+        Ok(inv_env)
+    }
 
-        match expr.kind.clone() {
-            VarRef { id } => {
-                varv.push(id.clone());
+    fn merge_loop_env(&self, mut loop_env: Env<'tcx>, expr: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>) {
+        let breaks = loop_env.take_exits(ExitKind::Break);
+        // Returns leave the callee, so the code after the loop runs only where none was taken
+        let returns = loop_env.take_exits(ExitKind::Return);
+        env.merge_env(loop_env);
+        if !returns.is_empty() {
+            let conds: Vec<String> = returns.iter().map(|exit| exit.cond.clone()).collect();
+            // A loop without a break is only left through its returns
+            if breaks.is_empty() {
+                env.add_assumption(Env::disjunction(&conds), expr.clone());
             }
-            LogicalOp { lhs, rhs, .. } => {
-                Analyzer::search_var(lhs.clone(), varv);
-                Analyzer::search_var(rhs.clone(), varv);
-            }
-            Unary { arg, .. } => {
-                Analyzer::search_var(arg.clone(), varv);
-            }
-            Binary { lhs, rhs, .. } => {
-                Analyzer::search_var(lhs.clone(), varv);
-                Analyzer::search_var(rhs.clone(), varv);
+            env.diverged.push(Env::disjunction(&conds));
+            env.exits.extend(returns);
+        }
+        if breaks.is_empty() {
+            env.diverged.push("true".to_string());
+            return;
+        }
+
+        let conds: Vec<String> = breaks.iter().map(|exit| exit.cond.clone()).collect();
+        env.add_assumption(Env::disjunction(&conds), expr.clone());
+        for (var_id, (var_str, ty)) in env.var_map.clone() {
+            let (last, rest) = breaks.split_last().expect("No break found");
+            let mut value = last.var_map.get(&var_id).expect("Var not found at break").0.clone();
+            for exit in rest.iter().rev() {
+                let exit_str = exit.var_map.get(&var_id).expect("Var not found at break").0.clone();
+                if exit_str != value {
+                    value = Analyzer::value_to_ite(exit.cond.clone(), exit_str, value);
+                }
             }
-            _ => {
-                println!("{:?}", expr.kind);
-                panic!("Unknown invariant pattern")
+            if value != var_str {
+                let new_var_str = self.symbols.fresh(&var_id);
+                env.add_lir(Lir::new_parameter(new_var_str.clone(), ty, expr.clone()));
+                env.add_assumption(format!("(= {} {})", new_var_str, value), expr.clone());
+                env.insert_var(&var_id, new_var_str, &ty);
             }
         }
     }
 
-    fn search_used_var(block: Rc<RExpr<'tcx>>) -> Vec<LocalVarId> {
-        let mut varv: Vec<LocalVarId> = Vec::new();
-        if let RExpr { kind: RExprKind::Block { stmts, expr }, .. } = block.as_ref() {
-            for stmt in stmts {
-                Analyzer::search_var_expr(stmt.clone(), &mut varv, false);
+    fn set_var_map(
+        &self, block: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
+    ) -> Result<(), AnalysisError> {
+        let mut varv = Analyzer::search_used_var(block.clone())?;
+        // A write through a `ref mut` alias goes to the variable it borrows from
+        let mut index = 0;
+        while index < varv.len() {
+            let base = env.aliases.get(&varv[index]).and_then(Analyzer::place_base);
+            if let Some(base) = base.filter(|base| !varv.contains(base)) {
+                varv.push(base);
             }
-            if let Some(expr) = expr {
-                Analyzer::search_var_expr(expr.clone(), &mut varv, false);
+            index += 1;
+        }
+        for var in varv.iter() {
+            if env.var_map.contains_key(var) {
+                env.new_var_version(var, block.clone(), &self.symbols);
             }
         }
-        varv
+        Ok(())
+    }
+
+    /// The variable `place` is part of
+    fn place_base(place: &Rc<RExpr<'tcx>>) -> Option<LocalVarId> {
+        use RExprKind::*;
+
+        match &place.kind {
+            VarRef { id } | UpvarRef { var_hir_id: id, .. } => Some(*id),
+            Field { lhs, .. } | Index { lhs, .. } => Analyzer::place_base(lhs),
+            Deref { arg } => Analyzer::place_base(arg),
+            PlaceTypeAscription { source, .. } => Analyzer::place_base(source),
+            _ => None,
+        }
+    }
+
+    /// Collects every variable the block may write to
+    fn search_used_var(block: Rc<RExpr<'tcx>>) -> Result<Vec<LocalVarId>, AnalysisError> {
+        let mut varv: Vec<LocalVarId> = Vec::new();
+        Analyzer::search_var_expr(block, &mut varv, false)?;
+        Ok(varv)
     }
 
-    fn search_var_expr(expr: Rc<RExpr<'tcx>>, varv: &mut Vec<LocalVarId>, is_assign: bool) {
+    /// With `is_assign`, `expr` is a place that is written to, and its base variable is collected.
+    /// Otherwise `expr` is only read, but may still contain assignments and `&mut` borrows.
+    fn search_var_expr(
+        expr: Rc<RExpr<'tcx>>, varv: &mut Vec<LocalVarId>, is_assign: bool,
+    ) -> Result<(), AnalysisError> {
         use RExprKind::*;
 
+        if is_assign {
+            return match &expr.kind {
+                VarRef { id } | UpvarRef { var_hir_id: id, .. } => {
+                    // Calling an `FnMut` closure writes to what it captured by `&mut`
+                    let closure_ty = matches!(expr.ty().kind(), TyKind::Closure(..));
+                    let own = (!closure_ty).then_some(*id);
+                    for id in own.into_iter().chain(Analyzer::mut_captures(expr.ty())) {
+                        if !varv.contains(&id) {
+                            varv.push(id);
+                        }
+                    }
+                    Ok(())
+                }
+                Field { lhs, .. } => Analyzer::search_var_expr(lhs.clone(), varv, true),
+                Index { lhs, index } => {
+                    Analyzer::search_var_expr(lhs.clone(), varv, true)?;
+                    Analyzer::search_var_expr(index.clone(), varv, false)
+                }
+                // A reborrow such as `&mut *(&mut s)` writes to what the inner borrow borrows
+                Deref { arg } | Borrow { arg, .. } => {
+                    Analyzer::search_var_expr(arg.clone(), varv, true)
+                }
+                PlaceTypeAscription { source, .. } => {
+                    Analyzer::search_var_expr(source.clone(), varv, true)
+                }
+                _ => Err(AnalysisError::UnsupportedPatternAt {
+                    pattern: "Assignment to a non-place expression".into(),
+                    span: expr.span,
+                }),
+            };
+        }
+
         match &expr.kind {
-            Literal { .. } => (),
-            VarRef { id } => {
-                if is_assign {
-                    varv.push(id.clone());
+            Literal { .. }
+            | NonHirLiteral { .. }
+            | ZstLiteral { .. }
+            | NamedConst { .. }
+            | ConstParam { .. }
+            | VarRef { .. }
+            | UpvarRef { .. }
+            | Pat { .. }
+            | Continue { .. } => (),
+            Assign { lhs, rhs } | AssignOp { lhs, rhs, .. } => {
+                Analyzer::search_var_expr(lhs.clone(), varv, true)?;
+                Analyzer::search_var_expr(rhs.clone(), varv, false)?;
+            }
+            Borrow { borrow_kind, arg } => {
+                let is_mut = matches!(borrow_kind, BorrowKind::Mut { .. });
+                Analyzer::search_var_expr(arg.clone(), varv, is_mut && Analyzer::is_place(arg))?;
+            }
+            Call { fun, args, .. } => {
+                Analyzer::search_var_expr(fun.clone(), varv, false)?;
+                for arg in args.iter() {
+                    Analyzer::search_var_expr(arg.clone(), varv, false)?;
                 }
             }
-            LogicalOp { lhs, rhs, .. } => {
-                Analyzer::search_var_expr(lhs.clone(), varv, is_assign);
-                Analyzer::search_var_expr(rhs.clone(), varv, is_assign);
+            Binary { lhs, rhs, .. } | LogicalOp { lhs, rhs, .. } => {
+                Analyzer::search_var_expr(lhs.clone(), varv, false)?;
+                Analyzer::search_var_expr(rhs.clone(), varv, false)?;
+            }
+            Index { lhs, index } => {
+                Analyzer::search_var_expr(lhs.clone(), varv, false)?;
+                Analyzer::search_var_expr(index.clone(), varv, false)?;
             }
-            Unary { arg, .. } => {
-                Analyzer::search_var_expr(arg.clone(), varv, is_assign);
+            Unary { arg, .. } | Deref { arg } => {
+                Analyzer::search_var_expr(arg.clone(), varv, false)?;
             }
-            Binary { lhs, rhs, .. } => {
-                Analyzer::search_var_expr(lhs.clone(), varv, is_assign);
-                Analyzer::search_var_expr(rhs.clone(), varv, is_assign);
+            Field { lhs, .. } => Analyzer::search_var_expr(lhs.clone(), varv, false)?,
+            Cast { source }
+            | PointerCoercion { source, .. }
+            | PlaceTypeAscription { source, .. }
+            | ValueTypeAscription { source, .. } => {
+                Analyzer::search_var_expr(source.clone(), varv, false)?;
             }
-            Call { .. } => (),
-            If { then, else_opt, .. } => {
-                Analyzer::search_var_expr(then.clone(), varv, is_assign);
+            If { cond, then, else_opt } => {
+                Analyzer::search_var_expr(cond.clone(), varv, false)?;
+                Analyzer::search_var_expr(then.clone(), varv, false)?;
                 if let Some(else_block) = else_opt {
-                    Analyzer::search_var_expr(else_block.clone(), varv, is_assign);
+                    Analyzer::search_var_expr(else_block.clone(), varv, false)?;
+                }
+            }
+            Loop { body } => Analyzer::search_var_expr(body.clone(), varv, false)?,
+            Block { stmts, expr } => {
+                for stmt in stmts.iter().chain(expr.iter()) {
+                    Analyzer::search_var_expr(stmt.clone(), varv, false)?;
+                }
+            }
+            Match { scrutinee, arms } => {
+                let binds_mut = arms.iter().any(|arm| match &arm.kind {
+                    Arm { pattern, .. } => Analyzer::binds_by_mut_ref(pattern),
+                    _ => false,
+                });
+                let is_assign = binds_mut && Analyzer::is_place(scrutinee);
+                Analyzer::search_var_expr(scrutinee.clone(), varv, is_assign)?;
+                for arm in arms.iter() {
+                    Analyzer::search_var_expr(arm.clone(), varv, false)?;
                 }
             }
-            LetStmt { initializer, .. } => {
+            Arm { guard, body, .. } => {
+                if let Some(guard) = guard {
+                    Analyzer::search_var_expr(guard.clone(), varv, false)?;
+                }
+                Analyzer::search_var_expr(body.clone(), varv, false)?;
+            }
+            LetBinding { expr, pat } => {
+                let is_assign = Analyzer::binds_by_mut_ref(pat) && Analyzer::is_place(expr);
+                Analyzer::search_var_expr(expr.clone(), varv, is_assign)?;
+            }
+            LetStmt { pattern, initializer, else_block } => {
                 if let Some(initializer) = initializer {
-                    Analyzer::search_var_expr(initializer.clone(), varv, is_assign);
+                    let is_assign =
+                        Analyzer::binds_by_mut_ref(pattern) && Analyzer::is_place(initializer);
+                    Analyzer::search_var_expr(initializer.clone(), varv, is_assign)?;
+                }
+                if let Some(else_block) = else_block {
+                    Analyzer::search_var_expr(else_block.clone(), varv, false)?;
                 }
             }
-            AssignOp { lhs, rhs, .. } => {
-                Analyzer::search_var_expr(lhs.clone(), varv, true);
-                Analyzer::search_var_expr(rhs.clone(), varv, false);
+            Break { value, .. } | Return { value } => {
+                if let Some(value) = value {
+                    Analyzer::search_var_expr(value.clone(), varv, false)?;
+                }
             }
-            Assign { lhs, rhs } => {
-                Analyzer::search_var_expr(lhs.clone(), varv, true);
-                Analyzer::search_var_expr(rhs.clone(), varv, false);
+            Repeat { value, .. } => Analyzer::search_var_expr(value.clone(), varv, false)?,
+            Array { fields } | Tuple { fields } => {
+                for field in fields.iter() {
+                    Analyzer::search_var_expr(field.clone(), varv, false)?;
+                }
             }
-            Block { stmts, expr } => {
-                for stmt in stmts {
-                    Analyzer::search_var_expr(stmt.clone(), varv, is_assign);
+            Closure { upvars, .. } => {
+                for upvar in upvars.iter() {
+                    Analyzer::search_var_expr(upvar.clone(), varv, false)?;
+                }
+            }
+            Adt { fields, base, .. } => {
+                for field in fields.iter() {
+                    Analyzer::search_var_expr(field.expr.clone(), varv, false)?;
+                }
+                if let Some(base) = base {
+                    Analyzer::search_var_expr(base.clone(), varv, false)?;
                 }
-                if let Some(expr) = expr {
-                    Analyzer::search_var_expr(expr.clone(), varv, false);
+            }
+        }
+        Ok(())
+    }
+
+    /// Variables a closure of type `ty` captures by mutable borrow
+    fn mut_captures(ty: Ty<'tcx>) -> Vec<LocalVarId> {
+        let TyKind::Closure(closure_id, _) = ty.kind() else {
+            return Vec::new();
+        };
+        ty::tls::with(|tcx| {
+            tcx.closure_captures(closure_id.expect_local())
+                .iter()
+                .filter(|place| {
+                    matches!(
+                        place.info.capture_kind,
+                        UpvarCapture::ByRef(
+                            ty::BorrowKind::MutBorrow | ty::BorrowKind::UniqueImmBorrow
+                        )
+                    )
+                })
+                .map(|place| LocalVarId(place.get_root_variable()))
+                .collect()
+        })
+    }
+
+    fn is_place(expr: &Rc<RExpr<'tcx>>) -> bool {
+        use RExprKind::*;
+
+        match &expr.kind {
+            VarRef { .. } | UpvarRef { .. } | Deref { .. } => true,
+            Field { lhs, .. } | Index { lhs, .. } => Analyzer::is_place(lhs),
+            PlaceTypeAscription { source, .. } => Analyzer::is_place(source),
+            _ => false,
+        }
+    }
+
+    /// An index of `place` that isn't a constant, so the element it picks may change
+    fn variable_index(place: &Rc<RExpr<'tcx>>) -> Option<Rc<RExpr<'tcx>>> {
+        use RExprKind::*;
+
+        match &place.kind {
+            Index { lhs, index } => match index.kind {
+                Literal { .. } | NonHirLiteral { .. } | NamedConst { .. } | ConstParam { .. } => {
+                    Analyzer::variable_index(lhs)
                 }
+                _ => Some(index.clone()),
+            },
+            Field { lhs, .. } => Analyzer::variable_index(lhs),
+            Deref { arg } => Analyzer::variable_index(arg),
+            PlaceTypeAscription { source, .. } => Analyzer::variable_index(source),
+            _ => None,
+        }
+    }
+
+    /// `ref mut` bindings write through to the matched place
+    fn binds_by_mut_ref(pat: &Rc<RExpr<'tcx>>) -> bool {
+        let RExprKind::Pat { kind } = &pat.kind else {
+            return false;
+        };
+        match kind {
+            RPatKind::Wild | RPatKind::Constant { .. } | RPatKind::Range(..) => false,
+            RPatKind::Binding { mode, subpattern, .. } => {
+                matches!(mode, BindingMode(ByRef::Yes(Mutability::Mut), _))
+                    || subpattern.as_ref().is_some_and(Analyzer::binds_by_mut_ref)
+            }
+            RPatKind::AscribeUserType { subpattern, .. }
+            | RPatKind::Deref { subpattern }
+            | RPatKind::DerefPattern { subpattern, .. } => Analyzer::binds_by_mut_ref(subpattern),
+            RPatKind::Variant { subpatterns, .. } | RPatKind::Leaf { subpatterns } => {
+                subpatterns.iter().any(|field_pat| Analyzer::binds_by_mut_ref(&field_pat.pattern))
             }
-            Break { .. } => (),
-            _ => panic!("Unknown pattern in loop: {:?}", expr),
+            RPatKind::Or { pats } => pats.iter().any(Analyzer::binds_by_mut_ref),
         }
     }
 }
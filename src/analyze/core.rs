@@ -12,55 +12,103 @@ impl<'tcx> Analyzer<'tcx> {
         Ok(())
     }
 
-    pub fn analyze_params(
-        &self, params: &Vec<RParam<'tcx>>, args: Box<[Rc<RExpr<'tcx>>]>, env: &mut Env<'tcx>,
-    ) -> Result<(), AnalysisError> {
-        use RExprKind::*;
-        use RPatKind::*;
-
+    /// The values of a call's arguments, evaluated in the caller before the callee is entered,
+    /// so the writes an argument makes stay with the caller. A `&mut` argument is only written
+    /// back when its param binds a variable, so it may not be passed to any other param.
+    pub fn args_to_constraint(
+        &self, params: &[RParam<'tcx>], args: &[Rc<RExpr<'tcx>>], env: &mut Env<'tcx>,
+    ) -> Result<Vec<String>, AnalysisError> {
+        let mut values = Vec::new();
         for (param, arg) in params.iter().zip(args.iter()) {
-            if let Some(pat) = &param.pat {
-                if let RExpr { kind: Pat { kind }, .. } = pat.as_ref() {
-                    match kind {
-                        Binding { name, ty, var, .. } => {
-                            let env_name = format!("{}_{}", env.name, name);
-                            env.add_parameter(env_name.clone(), ty, var, pat.clone());
-                            let value = self.expr_to_constraint(arg.clone(), env)?;
-                            env.add_assumption(format!("(= {} {})", env_name, value), arg.clone());
-                        }
-                        Wild => (),
-                        _ => return Err(AnalysisError::UnsupportedPattern(format!("{:?}", kind))),
+            let value = match param.pat.as_ref().map(|pat| &pat.kind) {
+                Some(RExprKind::Pat { kind: RPatKind::Binding { .. } }) => {
+                    self.borrowed_to_constraint(arg.clone(), env)?
+                }
+                _ => self.expr_to_constraint(arg.clone(), env)?,
+            };
+            values.push(value);
+        }
+        Ok(values)
+    }
+
+    /// The value of an argument the call writes back to: a `&mut` one is the place it borrows
+    pub fn borrowed_to_constraint(
+        &self, arg: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
+    ) -> Result<String, AnalysisError> {
+        match &arg.kind {
+            RExprKind::Borrow { borrow_kind: BorrowKind::Mut { .. }, arg: place } => {
+                match &place.kind {
+                    // A reborrow `&mut *r` borrows what `r` does
+                    RExprKind::Deref { arg: borrowed } => {
+                        self.borrowed_to_constraint(borrowed.clone(), env)
                     }
+                    _ => self.expr_to_constraint(place.clone(), env),
                 }
             }
+            _ => self.expr_to_constraint(arg, env),
         }
-        Ok(())
     }
 
-    pub fn analyze_body(
-        &self, body: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
+    pub fn bind_params(
+        &self, params: &[RParam<'tcx>], values: Vec<String>, env: &mut Env<'tcx>,
     ) -> Result<(), AnalysisError> {
-        if let RExpr { kind: RExprKind::Block { stmts, expr }, .. } = body.as_ref() {
-            let mut stmts_iter = stmts.iter().cloned().peekable();
-            while let Some(stmt) = stmts_iter.next() {
-                let return_value = self.analyze_expr(stmt.clone(), env)?;
-                match &return_value {
-                    AnalysisType::Return(..) => break,
-                    AnalysisType::Invariant(expr) => {
-                        self.analyze_loop(expr.clone(), &mut stmts_iter, env)?
-                    }
-                    AnalysisType::Other => (),
-                }
+        for (param, value) in params.iter().zip(values) {
+            if let Some(pat) = &param.pat {
+                self.bind_pattern(pat.clone(), Some(value), env)?;
             }
-            if let Some(expr) = expr {
-                self.analyze_expr(expr.clone(), env)?;
+        }
+        Ok(())
+    }
+
+    /// The arguments of a closure call, which the `Fn*` traits pass as one tuple
+    pub fn closure_args(
+        &self, args: &[Rc<RExpr<'tcx>>],
+    ) -> Result<Box<[Rc<RExpr<'tcx>>]>, AnalysisError> {
+        match &args[1].kind {
+            RExprKind::Tuple { fields } => Ok(fields.clone()),
+            _ => Err(AnalysisError::UnsupportedPatternAt {
+                pattern: "Closure call with untupled arguments".into(),
+                span: args[1].span,
+            }),
+        }
+    }
+
+    /// The final values of `&mut` params, with the places the caller borrowed for them
+    pub fn param_write_backs(
+        &self, params: &[RParam<'tcx>], args: &[Rc<RExpr<'tcx>>], env: &Env<'tcx>,
+    ) -> Vec<(Rc<RExpr<'tcx>>, String, Rc<RExpr<'tcx>>)> {
+        let mut write_backs = Vec::new();
+        for (param, arg) in params.iter().zip(args.iter()) {
+            let RExprKind::Borrow { borrow_kind: BorrowKind::Mut { .. }, arg: place } = &arg.kind
+            else {
+                continue;
+            };
+            if let Some(RExprKind::Pat { kind: RPatKind::Binding { var, .. } }) =
+                param.pat.as_ref().map(|pat| &pat.kind)
+            {
+                let (value, _) = env.get_var(var);
+                write_backs.push((place.clone(), value, arg.clone()));
             }
-        } else {
-            return Err(AnalysisError::UnsupportedPattern("Unknown body pattern".into()));
+        }
+        write_backs
+    }
+
+    pub fn write_back_params(
+        &self, write_backs: Vec<(Rc<RExpr<'tcx>>, String, Rc<RExpr<'tcx>>)>, env: &mut Env<'tcx>,
+    ) -> Result<(), AnalysisError> {
+        for (place, value, arg) in write_backs {
+            self.assign_to_place(place, value, arg, env)?;
         }
         Ok(())
     }
 
+    pub fn analyze_body(
+        &self, body: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
+    ) -> Result<(), AnalysisError> {
+        self.analyze_block(body, env)?;
+        Ok(())
+    }
+
     pub fn analyze_expr(
         &self, expr: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
     ) -> Result<AnalysisType<'tcx>, AnalysisError> {
@@ -70,7 +118,7 @@ impl<'tcx> Analyzer<'tcx> {
 
         match expr.kind.clone() {
             Literal { .. } => self.analyze_literal(expr, env)?,
-            VarRef { .. } => self.analyze_var_ref(expr, env)?,
+            VarRef { .. } | UpvarRef { .. } => self.analyze_var_ref(expr, env)?,
             Binary { .. } => self.analyze_binary(expr, env)?,
             Pat { kind } => self.analyze_pat(&kind, expr, env)?,
             Call { ty, args, .. } => return_value = self.analyze_fn(ty, args, expr, env)?,
@@ -78,16 +126,25 @@ impl<'tcx> Analyzer<'tcx> {
                 self.analyze_let_stmt(pattern, initializer, else_block, env)?
             }
             Return { value } => {
-                if let Some(expr) = value {
-                    return_value = AnalysisType::Return(Some(self.expr_to_constraint(expr, env)?));
-                } else {
-                    return_value = AnalysisType::Return(None);
-                }
+                let value = value.map(|expr| self.expr_to_constraint(expr, env)).transpose()?;
+                env.add_return(value.clone());
+                return_value = AnalysisType::Return(value);
             }
             AssignOp { op, lhs, rhs } => self.analyze_assign_op(op, lhs, rhs, expr, env)?,
             Assign { lhs, rhs } => self.analyze_assign(lhs, rhs, expr, env)?,
             If { cond, then, else_opt } => self.analyze_if(cond, then, else_opt, env)?,
-            // Break { .. } => (),
+            Match { scrutinee, arms } => self.analyze_match(scrutinee, &arms, env)?,
+            Loop { .. } => self.analyze_loop(Vec::new(), expr, &[], env)?,
+            Block { .. } => return_value = self.analyze_block(expr, env)?,
+            Break { .. } => {
+                env.add_exit(ExitKind::Break);
+                return_value = AnalysisType::Break;
+            }
+            Continue { .. } => {
+                env.add_exit(ExitKind::Continue);
+                return_value = AnalysisType::Continue;
+            }
+            Tuple { fields } if fields.is_empty() => (),
             _ => {
                 println!("{:?}", expr.kind);
                 return Err(AnalysisError::UnsupportedPattern("Unknown expr".into()));
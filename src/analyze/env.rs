@@ -8,7 +8,7 @@ use std::rc::Rc;
 use std::collections::{HashMap as Map, VecDeque};
 
 // Own crates
-use crate::analyze::{lir::*, AnalysisError};
+use crate::analyze::{lir::*, AnalysisError, Caller, ExitKind, LoopExit, Symbols};
 use crate::thir::rthir::*;
 
 #[derive(Clone)]
@@ -16,17 +16,28 @@ pub struct Env<'tcx> {
     pub name: String,
     pub path: VecDeque<Lir<'tcx>>,
     pub var_map: Map<LocalVarId, (String, Ty<'tcx>)>,
+    /// `ref mut` bindings and the places they borrow, which reads and writes go to instead
+    pub aliases: Map<LocalVarId, Rc<RExpr<'tcx>>>,
+    pub exits: Vec<LoopExit<'tcx>>,
+    pub diverged: Vec<String>,
 }
 
 impl<'tcx> Env<'tcx> {
     pub fn new() -> Self {
-        Self { name: "main".to_string(), path: VecDeque::new(), var_map: Map::new() }
+        Self {
+            name: "main".to_string(),
+            path: VecDeque::new(),
+            var_map: Map::new(),
+            aliases: Map::new(),
+            exits: Vec::new(),
+            diverged: Vec::new(),
+        }
     }
 
     pub fn from(
         name: String, path: VecDeque<Lir<'tcx>>, var_map: Map<LocalVarId, (String, Ty<'tcx>)>,
     ) -> Self {
-        Self { name, path, var_map }
+        Self { name, path, var_map, aliases: Map::new(), exits: Vec::new(), diverged: Vec::new() }
     }
 
     pub fn len(&self) -> usize { self.path.len() }
@@ -59,6 +70,10 @@ impl<'tcx> Env<'tcx> {
     pub fn get_latest_span(&self) -> Span { self.path.back().expect("Lir not fhound").get_span() }
 
     pub fn add_assumption(&mut self, constraint: String, expr: Rc<RExpr<'tcx>>) {
+        let constraint = match self.alive() {
+            Some(alive) => format!("(=> {} {})", alive, constraint),
+            None => constraint,
+        };
         let new_assumption = Lir::new_assume(constraint, expr);
         self.path.push_back(new_assumption);
     }
@@ -73,33 +88,15 @@ impl<'tcx> Env<'tcx> {
 
     pub fn add_lir(&mut self, lir: Lir<'tcx>) { self.path.push_back(lir) }
 
-    pub fn assign_new_value(
-        &mut self, expr: Rc<RExpr<'tcx>>,
-    ) -> Result<(String, String), AnalysisError> {
-        match expr.kind {
-            RExprKind::VarRef { id } => {
-                let (current_symbol, ty) = self.get_var(&id);
-                let new_symbol = if current_symbol.starts_with(self.name.as_str()) {
-                    format!("{}+", current_symbol)
-                } else {
-                    format!("{}_{}", self.name, current_symbol)
-                };
-                let new_parameter =
-                    Lir::new_parameter(new_symbol.clone(), ty.clone(), expr.clone());
-                self.add_lir(new_parameter);
-                self.insert_var(&id, new_symbol.clone(), &ty);
-                Ok((new_symbol, current_symbol))
-            }
-            _ => unreachable!(),
-        }
-    }
-
-    pub fn new_env_name(&self, name: &str) -> String {
-        if self.name.starts_with(name) {
-            format!("{}+", self.name)
-        } else {
-            name.to_string()
-        }
+    pub fn new_var_version(
+        &mut self, var_id: &LocalVarId, expr: Rc<RExpr<'tcx>>, symbols: &Symbols<'tcx>,
+    ) -> (String, String) {
+        let (current_symbol, ty) = self.get_var(var_id);
+        let new_symbol = symbols.fresh(var_id);
+        let new_parameter = Lir::new_parameter(new_symbol.clone(), ty, expr);
+        self.add_lir(new_parameter);
+        self.insert_var(var_id, new_symbol.clone(), &ty);
+        (new_symbol, current_symbol)
     }
 
     pub fn insert_var(&mut self, var_id: &LocalVarId, name: String, ty: &Ty<'tcx>) {
@@ -112,56 +109,263 @@ impl<'tcx> Env<'tcx> {
 
     pub fn merge_env(&mut self, env: Env<'tcx>) {
         for assumption in env.path.iter() {
-            match assumption.kind {
-                LirKind::Declaration { .. } => self.add_lir(assumption.clone()),
-                LirKind::Assume(_) => self.add_lir(assumption.clone()),
+            match &assumption.kind {
+                LirKind::Declaration { .. }
+                | LirKind::Datatype { .. }
+                | LirKind::Definition(..) => self.add_lir(assumption.clone()),
+                LirKind::Assume(constraint) => {
+                    self.add_assumption(constraint.clone(), assumption.expr.clone())
+                }
                 _ => (),
             }
         }
+        self.var_map.retain(|var_id, _| env.var_map.contains_key(var_id));
+    }
+
+    /// Condition under which control is still in this env, if some path already left it
+    pub fn alive(&self) -> Option<String> {
+        if self.diverged.is_empty() {
+            None
+        } else {
+            Some(format!("(not {})", Env::disjunction(&self.diverged)))
+        }
+    }
+
+    pub fn is_unreachable(&self) -> bool { self.diverged.iter().any(|cond| cond == "true") }
+
+    pub fn add_exit(&mut self, kind: ExitKind) { self.add_exit_with_value(kind, None); }
+
+    pub fn add_return(&mut self, value: Option<String>) {
+        self.add_exit_with_value(ExitKind::Return, value);
+    }
+
+    fn add_exit_with_value(&mut self, kind: ExitKind, value: Option<String>) {
+        let cond = self.alive().unwrap_or_else(|| "true".to_string());
+        self.exits.push(LoopExit { kind, cond, var_map: self.var_map.clone(), value });
+        self.diverged.push("true".to_string());
+    }
+
+    pub fn take_exits(&mut self, kind: ExitKind) -> Vec<LoopExit<'tcx>> {
+        let (taken, rest) = self.exits.drain(..).partition(|exit| exit.kind == kind);
+        self.exits = rest;
+        taken
+    }
+
+    /// `continue` goes back to the loop head like the end of the body does
+    pub fn resume_continues(&mut self, expr: Rc<RExpr<'tcx>>, symbols: &Symbols<'tcx>) {
+        let continues = self.take_exits(ExitKind::Continue);
+        if continues.is_empty() {
+            return;
+        }
+        self.revive(&continues);
+        self.merge_exit_vars(&continues, expr, symbols);
+    }
+
+    /// The paths that took `exits` are alive again where they lead
+    fn revive(&mut self, exits: &[LoopExit<'tcx>]) {
+        let conds: Vec<String> = exits.iter().map(|exit| exit.cond.clone()).collect();
+        let diverged = Env::disjunction(&self.diverged);
+        self.diverged = vec![format!("(and {} (not {}))", diverged, Env::disjunction(&conds))];
+    }
+
+    /// Sets aside the caller's state while a callee body is analyzed,
+    /// naming the symbols declared from now on after the callee `name`
+    pub fn enter_fn(&mut self, name: &str) -> Caller<'tcx> {
+        let caller = Caller {
+            name: self.name.clone(),
+            diverged: self.diverged.clone(),
+            returns: self.take_exits(ExitKind::Return),
+            var_map: self.var_map.clone(),
+            aliases: self.aliases.clone(),
+            mut_captures: Vec::new(),
+        };
+        self.name = name.to_string();
+        caller
+    }
+
+    pub fn enter_closure(&mut self, mut_captures: Vec<LocalVarId>) -> Caller<'tcx> {
+        Caller { mut_captures, ..self.enter_fn("closure") }
+    }
+
+    /// Condition under which a callee body that can't fall through returned
+    pub fn returned(&self) -> Option<String> {
+        let conds: Vec<String> = self
+            .exits
+            .iter()
+            .filter(|exit| exit.kind == ExitKind::Return)
+            .map(|exit| exit.cond.clone())
+            .collect();
+        (self.is_unreachable() && !conds.is_empty()).then(|| Env::disjunction(&conds))
+    }
+
+    /// The value of a callee body ending with `tail`, merged with its return points.
+    /// Variables take their value at the return point taken as well.
+    pub fn resume_returns(
+        &mut self, tail: String, expr: Rc<RExpr<'tcx>>, symbols: &Symbols<'tcx>,
+    ) -> String {
+        let returns = self.take_exits(ExitKind::Return);
+        if returns.is_empty() {
+            return tail;
+        }
+        let mut value = tail;
+        for exit in returns.iter().rev() {
+            if let Some(returned) = &exit.value {
+                if value.is_empty() {
+                    value = returned.clone();
+                } else if returned != &value {
+                    value = format!("(ite {} {} {})", exit.cond, returned, value);
+                }
+            }
+        }
+        self.revive(&returns);
+        self.merge_exit_vars(&returns, expr, symbols);
+        value
+    }
 
-        let mut new_var_map = Map::new();
-        let current_var_map = self.var_map.clone();
-        for (var_id, (var_str, ty)) in current_var_map.iter() {
-            if let Some((env_var_str, ..)) = env.var_map.get(var_id) {
-                if var_str != env_var_str {
-                    let new_var_str = format!("{}+", var_str);
-                    let last = env.path.back().expect("No lir found in new_var_map").expr.clone();
-                    self.add_lir(Lir::new_parameter(new_var_str.clone(), ty.clone(), last.clone()));
-                    self.add_lir(Lir::new_assume(
-                        format!("(= {} {})", new_var_str, var_str),
-                        last.clone(),
-                    ));
-                    self.add_lir(Lir::new_assume(
-                        format!("(= {} {})", new_var_str, env_var_str),
-                        last.clone(),
-                    ));
-                    new_var_map.insert(var_id.clone(), (new_var_str, ty.clone()));
-                } else {
-                    new_var_map.insert(var_id.clone(), (var_str.clone(), ty.clone()));
+    /// Back in the caller, which is alive again wherever it was before the call.
+    /// The callee's locals go out of scope, and with them the bindings a recursive call made
+    /// for the caller's own; variables captured by `&mut` keep what a closure wrote to them.
+    pub fn leave_body(&mut self, caller: Caller<'tcx>) {
+        self.name = caller.name;
+        self.diverged = caller.diverged;
+        self.exits.extend(caller.returns);
+        self.aliases = caller.aliases;
+        let mut var_map = caller.var_map;
+        for var_id in caller.mut_captures {
+            if let Some(var) = self.var_map.remove(&var_id) {
+                var_map.insert(var_id, var);
+            }
+        }
+        self.var_map = var_map;
+    }
+
+    /// Gives each variable its value at the exit taken, or its current one if none was
+    fn merge_exit_vars(
+        &mut self, exits: &[LoopExit<'tcx>], expr: Rc<RExpr<'tcx>>, symbols: &Symbols<'tcx>,
+    ) {
+        if exits.is_empty() {
+            return;
+        }
+        for (var_id, (var_str, ty)) in self.var_map.clone() {
+            let mut value = var_str.clone();
+            for exit in exits.iter().rev() {
+                if let Some((exit_str, _)) = exit.var_map.get(&var_id) {
+                    if exit_str != &value {
+                        value = format!("(ite {} {} {})", exit.cond, exit_str, value);
+                    }
                 }
             }
+            if value != var_str {
+                let new_var_str = symbols.fresh(&var_id);
+                self.add_lir(Lir::new_parameter(new_var_str.clone(), ty, expr.clone()));
+                self.add_assumption(format!("(= {} {})", new_var_str, value), expr.clone());
+                self.insert_var(&var_id, new_var_str, &ty);
+            }
+        }
+    }
+
+    /// Merge the env of the next unrolled iteration, which runs where this one is still alive
+    pub fn merge_iteration_env(&mut self, env: Env<'tcx>) {
+        self.merge_env(env.clone());
+        self.lift_exits("true", &env);
+        self.lift_divergence("true", &env);
+    }
+
+    fn lift_exits(&mut self, cond_str: &str, env: &Env<'tcx>) {
+        let mut outer = vec![cond_str.to_string()];
+        outer.extend(self.alive());
+        for exit in env.exits.iter() {
+            let mut conds = outer.clone();
+            conds.push(exit.cond.clone());
+            self.exits.push(LoopExit { cond: Env::conjunction(&conds), ..exit.clone() });
+        }
+    }
+
+    fn lift_divergence(&mut self, cond_str: &str, env: &Env<'tcx>) {
+        if !env.diverged.is_empty() {
+            let diverged = Env::disjunction(&env.diverged);
+            self.diverged.push(Env::conjunction(&[cond_str.to_string(), diverged]));
+        }
+    }
+
+    pub fn conjunction(conds: &[String]) -> String {
+        let conds: Vec<String> = conds.iter().filter(|cond| *cond != "true").cloned().collect();
+        match conds.len() {
+            0 => "true".to_string(),
+            1 => conds[0].clone(),
+            _ => format!("(and {})", conds.join(" ")),
+        }
+    }
+
+    pub fn disjunction(conds: &[String]) -> String {
+        if conds.iter().any(|cond| cond == "true") {
+            return "true".to_string();
+        }
+        match conds.len() {
+            0 => "false".to_string(),
+            1 => conds[0].clone(),
+            _ => format!("(or {})", conds.join(" ")),
         }
-        self.var_map = new_var_map;
     }
 
     pub fn gen_new_env(
         &self, name: String, expr: Rc<RExpr<'tcx>>,
     ) -> Result<Env<'tcx>, AnalysisError> {
-        let name = self.new_env_name(&name);
-        let assumptions = Lir::new_assumptions(self.get_assumptions()?, expr);
+        let assumptions = Lir::new_assumptions(self.get_assumptions()?, expr.clone());
         let mut new_path = VecDeque::new();
         new_path.push_back(assumptions);
-        Ok(Env::from(name, new_path, self.var_map.clone()))
+        let mut env = Env::from(name, new_path, self.var_map.clone());
+        env.aliases = self.aliases.clone();
+        if let Some(alive) = self.alive() {
+            env.add_assumption(alive, expr);
+        }
+        Ok(env)
     }
 
     pub fn merge_then_else_env(
-        &mut self, cond_str: String, mut then_env: Env<'tcx>, else_env: Option<Env<'tcx>>,
+        &mut self, cond_str: String, mut then_env: Env<'tcx>, mut else_env: Option<Env<'tcx>>,
+        symbols: &Symbols<'tcx>,
     ) -> Result<(), AnalysisError> {
+        let not_cond_str = format!("(not {})", cond_str);
+        let last = then_env.path.back().expect("No lir found in then env").expr.clone();
+        let var_map = self.var_map.clone();
+
         then_env.adapt_cond_to_path(&cond_str)?;
-        self.merge_env(then_env);
-        if let Some(mut else_env) = else_env {
-            else_env.adapt_cond_to_path(&format!("(not {})", cond_str))?;
-            self.merge_env(else_env);
+        self.merge_env(then_env.clone());
+        if let Some(else_env) = &mut else_env {
+            else_env.adapt_cond_to_path(&not_cond_str)?;
+            self.merge_env(else_env.clone());
+        }
+        self.lift_exits(&cond_str, &then_env);
+        if let Some(else_env) = &else_env {
+            self.lift_exits(&not_cond_str, else_env);
+        }
+
+        let else_unreachable = else_env.as_ref().is_some_and(|env| env.is_unreachable());
+        for (var_id, (var_str, ty)) in var_map.iter() {
+            let then_str = then_env.get_var(var_id).0;
+            let else_str = match &else_env {
+                Some(else_env) => else_env.get_var(var_id).0,
+                None => var_str.clone(),
+            };
+            let value = if then_env.is_unreachable() {
+                else_str
+            } else if else_unreachable || then_str == else_str {
+                then_str
+            } else {
+                format!("(ite {} {} {})", cond_str, then_str, else_str)
+            };
+            if &value != var_str {
+                let new_var_str = symbols.fresh(var_id);
+                self.add_lir(Lir::new_parameter(new_var_str.clone(), *ty, last.clone()));
+                self.add_assumption(format!("(= {} {})", new_var_str, value), last.clone());
+                self.insert_var(var_id, new_var_str, ty);
+            }
+        }
+
+        self.lift_divergence(&cond_str, &then_env);
+        if let Some(else_env) = &else_env {
+            self.lift_divergence(&not_cond_str, else_env);
         }
         Ok(())
     }
@@ -170,7 +374,9 @@ impl<'tcx> Env<'tcx> {
         let mut adapted_path = VecDeque::new();
         for lir in self.path.iter() {
             match &lir.kind {
-                LirKind::Declaration { .. } => adapted_path.push_back(lir.clone()),
+                LirKind::Declaration { .. }
+                | LirKind::Datatype { .. }
+                | LirKind::Definition(..) => adapted_path.push_back(lir.clone()),
                 LirKind::Assume(constraint) => {
                     if constraint != cond_str {
                         adapted_path.push_back(Lir::new_assume(
@@ -1,4 +1,9 @@
 // rustc crates
+use rustc_hir::RangeEnd;
+use rustc_middle::mir;
+use rustc_middle::thir::{self, PatRangeBoundary};
+use rustc_middle::ty::{ParamEnv, ScalarInt};
+
 // std crates
 // Own crates
 use crate::analyze::*;
@@ -10,11 +15,37 @@ impl<'tcx> Analyzer<'tcx> {
         use RExprKind::*;
 
         match &arg.kind {
+            // Closures are only inlined where they are called through the let that binds them
+            _ if matches!(arg.ty().peel_refs().kind(), TyKind::Closure(..)) => {
+                Err(AnalysisError::UnsupportedPatternAt {
+                    pattern: "Closure passed as a value; call it where it is bound".into(),
+                    span: arg.span,
+                })
+            }
             Literal { lit, neg } => Ok(Analyzer::literal_to_constraint(lit, *neg)?),
-            VarRef { id } => self.var_ref_to_constraint(id, env),
+            VarRef { id } | UpvarRef { var_hir_id: id, .. } => self.var_ref_to_constraint(id, env),
+            NamedConst { def_id, args, .. } => {
+                let constant =
+                    mir::Const::Unevaluated(mir::UnevaluatedConst::new(*def_id, args), arg.ty());
+                self.const_to_constraint(constant, arg.ty())
+            }
+            NonHirLiteral { lit, .. } => Analyzer::scalar_int_to_constraint(*lit, arg.ty()),
+            ConstParam { param, .. } => {
+                let (symbol, declaration) = const_param_symbol(param.name.as_str());
+                env.add_lir(Lir::new_definition(declaration, arg.clone()));
+                Ok(symbol)
+            }
             LogicalOp { op, lhs, rhs } => {
                 let lhs_str = self.expr_to_constraint(lhs.clone(), env)?;
-                let rhs_str = self.expr_to_constraint(rhs.clone(), env)?;
+                // The rhs is only evaluated where the lhs doesn't decide the result
+                let cond_str = match op {
+                    thir::LogicalOp::And => lhs_str.clone(),
+                    thir::LogicalOp::Or => format!("(not {})", lhs_str),
+                };
+                let mut rhs_env = env.gen_new_env("rhs".to_string(), rhs.clone())?;
+                rhs_env.add_assumption(cond_str.clone(), lhs.clone());
+                let rhs_str = self.expr_to_constraint(rhs.clone(), &mut rhs_env)?;
+                env.merge_then_else_env(cond_str, rhs_env, None, &self.symbols)?;
                 Ok(self.logical_op_to_constraint(*op, &lhs_str, &rhs_str)?)
             }
             Unary { op, arg } => {
@@ -24,12 +55,113 @@ impl<'tcx> Analyzer<'tcx> {
             Binary { op, lhs, rhs } => {
                 let lhs_str = self.expr_to_constraint(lhs.clone(), env)?;
                 let rhs_str = self.expr_to_constraint(rhs.clone(), env)?;
-                Ok(self.bin_op_to_constraint(*op, &lhs_str, &rhs_str)?)
+                Ok(self.bin_op_to_constraint(*op, lhs.ty(), &lhs_str, &rhs_str)?)
+            }
+            Call { ty, args, .. } if self.is_ctor(*ty) => {
+                self.ctor_to_constraint(*ty, args, arg.clone(), env)
+            }
+            Call { ty, args, .. } => self.fn_to_constraint(*ty, args.clone(), arg.clone(), env),
+            Return { value } => {
+                let value =
+                    value.clone().map(|expr| self.expr_to_constraint(expr, env)).transpose()?;
+                env.add_return(value.clone());
+                Ok(value.unwrap_or_default())
             }
-            Call { ty, args, .. } => self.fn_to_constraint(*ty, args.clone(), env),
             If { cond, then, else_opt } => {
                 Ok(self.if_to_constraint(cond.clone(), then.clone(), else_opt.clone(), env)?)
             }
+            Match { scrutinee, arms } => {
+                let value = self.expr_to_constraint(scrutinee.clone(), env)?;
+                self.arms_to_constraint(&value, arms, env)
+            }
+            Tuple { fields } if !fields.is_empty() => {
+                env.add_lir(Lir::new_datatype(arg.ty(), arg.clone()));
+                let mut field_strs = Vec::new();
+                for field in fields.iter() {
+                    field_strs.push(self.expr_to_constraint(field.clone(), env)?);
+                }
+                Ok(constructor_application(arg.ty(), FIRST_VARIANT, &field_strs))
+            }
+            Adt { adt_def, variant_index, fields, base, .. } => {
+                env.add_lir(Lir::new_datatype(arg.ty(), arg.clone()));
+                let base_str = match base {
+                    Some(base) => Some(self.expr_to_constraint(base.clone(), env)?),
+                    None => None,
+                };
+                let mut field_strs = Vec::new();
+                for index in adt_def.variant(*variant_index).fields.indices() {
+                    match fields.iter().find(|field| field.name == index) {
+                        Some(field) => {
+                            field_strs.push(self.expr_to_constraint(field.expr.clone(), env)?)
+                        }
+                        None => {
+                            // Functional update: the other fields come from the base
+                            let accessor = field_accessor(arg.ty(), *variant_index, index);
+                            let base_str = base_str.as_ref().expect("Base of struct not found");
+                            field_strs.push(format!("({} {})", accessor, base_str));
+                        }
+                    }
+                }
+                Ok(constructor_application(arg.ty(), *variant_index, &field_strs))
+            }
+            Array { fields } => {
+                env.add_lir(Lir::new_datatype(arg.ty(), arg.clone()));
+                // An empty array has no element to read, so any array of its sort is it
+                if fields.is_empty() {
+                    return Ok(self.fresh_constant("array", arg, env));
+                }
+                // Built from the constant array of the first element, so no default is needed
+                let first = self.expr_to_constraint(fields[0].clone(), env)?;
+                let mut array = format!("((as const {}) {})", ty_to_sort(arg.ty())?, first);
+                for (index, field) in fields.iter().enumerate().skip(1) {
+                    let field_str = self.expr_to_constraint(field.clone(), env)?;
+                    array = format!("(store {} {} {})", array, index, field_str);
+                }
+                Ok(array)
+            }
+            Repeat { value, .. } => {
+                env.add_lir(Lir::new_datatype(arg.ty(), arg.clone()));
+                let value_str = self.expr_to_constraint(value.clone(), env)?;
+                Ok(format!("((as const {}) {})", ty_to_sort(arg.ty())?, value_str))
+            }
+            Index { lhs, index } => {
+                let lhs_str = self.expr_to_constraint(lhs.clone(), env)?;
+                let index_str = self.expr_to_constraint(index.clone(), env)?;
+                if seq_elem_ty(lhs.ty()).is_some() {
+                    let len = format!("(seq.len {})", lhs_str);
+                    self.verify_index(&index_str, &len, arg.clone(), env)?;
+                    Ok(format!("(seq.nth {} {})", lhs_str, index_str))
+                } else {
+                    let len = self.array_len_to_constraint(lhs.ty(), arg.clone(), env)?;
+                    self.verify_index(&index_str, &len, arg.clone(), env)?;
+                    Ok(format!("(select {} {})", lhs_str, index_str))
+                }
+            }
+            // A trait object hides which impl its method calls run
+            PointerCoercion { .. }
+                if matches!(arg.ty().peel_refs().kind(), TyKind::Dynamic(..)) =>
+            {
+                Err(AnalysisError::UnresolvedCall {
+                    callee: arg.ty().peel_refs().to_string(),
+                    span: arg.span,
+                })
+            }
+            // A `&mut` is only followed as a call argument that the call writes back to
+            Borrow { borrow_kind: BorrowKind::Mut { .. }, .. } => {
+                Err(AnalysisError::UnsupportedPatternAt {
+                    pattern: "Mutable borrow that is not written back by a call".into(),
+                    span: arg.span,
+                })
+            }
+            // A reference is its referent
+            Borrow { arg, .. } => self.expr_to_constraint(arg.clone(), env),
+            Deref { arg } if matches!(arg.ty().kind(), TyKind::Ref(..)) => {
+                self.expr_to_constraint(arg.clone(), env)
+            }
+            Field { lhs, variant_index, name } => {
+                let lhs_str = self.expr_to_constraint(lhs.clone(), env)?;
+                Ok(format!("({} {})", field_accessor(lhs.ty(), *variant_index, *name), lhs_str))
+            }
             _ => {
                 println!("{}", env.get_assumptions()?);
                 Err(AnalysisError::UnsupportedPattern(format!("name: {:?}", arg.kind)))
@@ -57,9 +189,12 @@ impl<'tcx> Analyzer<'tcx> {
     }
 
     pub fn var_ref_to_constraint(
-        &self, id: &LocalVarId, env: &Env<'tcx>,
+        &self, id: &LocalVarId, env: &mut Env<'tcx>,
     ) -> Result<String, AnalysisError> {
-        Ok(env.get_var(id).0)
+        match env.aliases.get(id).cloned() {
+            Some(place) => self.expr_to_constraint(place, env),
+            None => Ok(env.get_var(id).0),
+        }
     }
 
     pub fn logical_op_to_constraint(
@@ -85,16 +220,31 @@ impl<'tcx> Analyzer<'tcx> {
         Ok(format!("({} {})", op_str, arg_str))
     }
 
+    /// `ty` is the type of the operands
     pub fn bin_op_to_constraint(
-        &self, op: BinOp, lhs_str: &String, rhs_str: &String,
+        &self, op: BinOp, ty: Ty<'tcx>, lhs_str: &String, rhs_str: &String,
     ) -> Result<String, AnalysisError> {
         use BinOp::*;
 
         let op_str = match op {
+            // Rust's integer `/` truncates, while SMT's `div` keeps the remainder non-negative
+            Div if ty.is_integral() => {
+                return Ok(format!(
+                    "(ite (>= {0} 0) (div {0} {1}) (- (div (- {0}) {1})))",
+                    lhs_str, rhs_str
+                ))
+            }
+            // Rust's integer `%` truncates, so the result has the sign of the dividend
+            Rem if ty.is_integral() => {
+                return Ok(format!(
+                    "(ite (>= {0} 0) (mod {0} {1}) (- (mod (- {0}) {1})))",
+                    lhs_str, rhs_str
+                ))
+            }
+            Ne => return Ok(format!("(not (= {} {}))", lhs_str, rhs_str)),
             Add => "+",
             Sub => "-",
             Mul => "*",
-            Rem => "%",
             Div => "/",
             BitXor => "^",
             BitAnd => "&",
@@ -102,7 +252,6 @@ impl<'tcx> Analyzer<'tcx> {
             Eq => "=",
             Lt => "<",
             Le => "<=",
-            Ne => "!=",
             Ge => ">=",
             Gt => ">",
             _ => return Err(AnalysisError::UnsupportedPattern(format!("{:?}", op))),
@@ -111,40 +260,156 @@ impl<'tcx> Analyzer<'tcx> {
     }
 
     pub fn fn_to_constraint(
-        &self, ty: Ty<'tcx>, args: Box<[Rc<RExpr<'tcx>>]>, env: &mut Env<'tcx>,
+        &self, ty: Ty<'tcx>, args: Box<[Rc<RExpr<'tcx>>]>, expr: Rc<RExpr<'tcx>>,
+        env: &mut Env<'tcx>,
     ) -> Result<String, AnalysisError> {
         match ty.kind() {
-            TyKind::FnDef(def_id, ..) => {
+            TyKind::FnDef(def_id, fn_args) => {
+                let (def_id, fn_args) = self.resolve_fn(*def_id, fn_args, expr.span)?;
+                let def_id = &def_id;
                 let fn_info = self.get_fn_info(def_id);
-                if let Some(fun) = self.get_local_fn(def_id) {
-                    self.local_fn_to_constraint(fun.clone(), args, env)
+                if let Some(closure_id) = self.called_closure(ty) {
+                    self.closure_to_constraint(closure_id, args, expr, env)
+                } else if let Some(fun) = self.get_local_fn(def_id, fn_args) {
+                    let fn_name = fn_info.last().expect("fn info not found");
+                    let callee = (def_id.expect_local(), fn_args);
+                    self.local_fn_to_constraint(fn_name, callee, fun.clone(), args, expr, env)
                 } else {
-                    self.extern_fn_to_constraint(fn_info, args)
+                    self.extern_fn_to_constraint(fn_info, args, expr, env)
                 }
             }
             _ => panic!("Call has not have FnDef"),
         }
     }
 
+    /// A ctor call is the constructor applied to the arguments, in field order
+    pub fn ctor_to_constraint(
+        &self, ty: Ty<'tcx>, args: &[Rc<RExpr<'tcx>>], expr: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
+    ) -> Result<String, AnalysisError> {
+        let (TyKind::FnDef(def_id, ..), TyKind::Adt(adt_def, _)) = (ty.kind(), expr.ty().kind())
+        else {
+            return Err(AnalysisError::UnsupportedPattern(format!("Ctor: {}", ty)));
+        };
+        let variant_index = adt_def.variant_index_with_ctor_id(*def_id);
+        env.add_lir(Lir::new_datatype(expr.ty(), expr.clone()));
+        let mut field_strs = Vec::new();
+        for arg in args.iter() {
+            field_strs.push(self.expr_to_constraint(arg.clone(), env)?);
+        }
+        Ok(constructor_application(expr.ty(), variant_index, &field_strs))
+    }
+
+    /// A closure call is its body, reading the captured variables in place and writing those
+    /// captured by `&mut`. One captured by value is the closure's own copy, which isn't modeled.
+    pub fn closure_to_constraint(
+        &self, closure_id: LocalDefId, args: Box<[Rc<RExpr<'tcx>>]>, expr: Rc<RExpr<'tcx>>,
+        env: &mut Env<'tcx>,
+    ) -> Result<String, AnalysisError> {
+        let closure = self.get_fn(closure_id)?;
+        let closure_args = self.closure_args(&args)?;
+        let body = closure.body.as_ref().expect("Body not found").clone();
+        let mut written = Vec::new();
+        Analyzer::search_var_expr(body.clone(), &mut written, false)?;
+        if let Some(place) = self.tcx.closure_captures(closure_id).iter().find(|place| {
+            matches!(place.info.capture_kind, UpvarCapture::ByValue)
+                && written.contains(&LocalVarId(place.get_root_variable()))
+        }) {
+            return Err(AnalysisError::UnsupportedPatternAt {
+                pattern: "Write to a variable captured by value".into(),
+                span: place.get_path_span(self.tcx),
+            });
+        }
+        let mut_captures = Analyzer::mut_captures(args[0].ty().peel_refs());
+        let enter = |env: &mut Env<'tcx>| env.enter_closure(mut_captures);
+        // The first param is the closure itself
+        let params = &closure.params[1..];
+        self.call_to_constraint(enter, params, body, &closure_args, expr, env)
+    }
+
+    /// A fn with a contract is called through it, and one without is inlined. A call made while
+    /// the fn is already being inlined recurses, so only the unfold bound stops it.
     pub fn local_fn_to_constraint(
-        &self, expr: Rc<RThir<'tcx>>, args: Box<[Rc<RExpr<'tcx>>]>, env: &mut Env<'tcx>,
+        &self, name: &str, callee: (LocalDefId, GenericArgsRef<'tcx>), fun: Rc<RThir<'tcx>>,
+        args: Box<[Rc<RExpr<'tcx>>]>, expr: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
     ) -> Result<String, AnalysisError> {
-        self.analyze_params(&expr.params, args, env)?;
-        self.block_to_constraint(expr.body.as_ref().expect("Body not found").clone(), env)
+        let body = fun.body.as_ref().expect("Body not found").clone();
+        let (contract, body) = self.split_contract(body);
+        if !contract.is_empty() {
+            self.verify_contract(name, callee, &fun.params, &contract, body)?;
+            return self.contract_to_constraint(name, &fun.params, &contract, &args, expr, env);
+        }
+        let enter = |env: &mut Env<'tcx>| env.enter_fn(name);
+        let depth = self.unfolding.borrow().get(&callee.0).copied().unwrap_or(0);
+        if depth >= (*UNFOLD.get().unwrap_or(&DEFAULT_UNFOLD)).max(1) {
+            return Ok(self.cut_recursion(expr, env));
+        }
+        self.unfolding.borrow_mut().insert(callee.0, depth + 1);
+        let value = self.call_to_constraint(enter, &fun.params, body, &args, expr, env);
+        self.unfolding.borrow_mut().insert(callee.0, depth);
+        value
+    }
+
+    /// A call is inlined wherever it appears, so the callee's obligations are checked there.
+    /// The callee's params and locals are named after it, so methods can all bind `self`,
+    /// and its result is bound to a fresh symbol.
+    fn call_to_constraint(
+        &self, enter: impl FnOnce(&mut Env<'tcx>) -> Caller<'tcx>, params: &[RParam<'tcx>],
+        body: Rc<RExpr<'tcx>>, args: &[Rc<RExpr<'tcx>>], expr: Rc<RExpr<'tcx>>,
+        env: &mut Env<'tcx>,
+    ) -> Result<String, AnalysisError> {
+        let values = self.args_to_constraint(params, args, env)?;
+        let caller = enter(env);
+        let result = format!("{}_result{}", env.name, self.fresh_id());
+        let value = self
+            .bind_params(params, values, env)
+            .and_then(|()| self.callee_body_to_constraint(body.clone(), env))
+            .map(|value| (env.returned(), env.resume_returns(value, body, &self.symbols)));
+        let write_backs = self.param_write_backs(params, args, env);
+        env.leave_body(caller);
+        let (returned, value) = value?;
+        // Where the caller is alive, one of the returns was taken
+        if let Some(returned) = returned {
+            env.add_assumption(returned, expr.clone());
+        }
+        self.write_back_params(write_backs, env)?;
+        if value.is_empty() {
+            return Ok(value);
+        }
+        env.add_lir(Lir::new_parameter(result.clone(), expr.ty(), expr.clone()));
+        env.add_assumption(format!("(= {} {})", result, value), expr);
+        Ok(result)
+    }
+
+    /// A unit body is analyzed for its effects only, and a closure body need not be a block
+    pub fn callee_body_to_constraint(
+        &self, body: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
+    ) -> Result<String, AnalysisError> {
+        if !body.ty().is_unit() {
+            return self.branch_to_constraint(body, env);
+        }
+        match body.kind {
+            RExprKind::Block { .. } => self.analyze_block(body, env)?,
+            _ => self.analyze_expr(body, env)?,
+        };
+        Ok(String::new())
     }
 
     pub fn extern_fn_to_constraint(
-        &self, fn_info: Vec<String>, _: Box<[Rc<RExpr<'tcx>>]>,
+        &self, fn_info: Vec<String>, args: Box<[Rc<RExpr<'tcx>>]>, expr: Rc<RExpr<'tcx>>,
+        env: &mut Env<'tcx>,
     ) -> Result<String, AnalysisError> {
         if fn_info[0] == "t3modules" {
             match fn_info[1].as_str() {
-                "rand_bool" => Err(AnalysisError::RandFunctions),
-                "rand_int" => Err(AnalysisError::RandFunctions),
-                "rand_float" => Err(AnalysisError::RandFunctions),
+                // Each call is a fresh constant of the type it returns
+                "rand_bool" | "rand_int" | "rand_float" => {
+                    Ok(self.fresh_constant(&fn_info[1], expr, env))
+                }
                 _ => unreachable!(),
             }
+        } else if Analyzer::is_panic(&fn_info) {
+            self.panic_to_constraint(expr, env)
         } else {
-            Err(AnalysisError::UnsupportedPattern("Unknown function!".into()))
+            self.model_to_constraint(&fn_info, &args, expr, env)
         }
     }
 
@@ -152,37 +417,249 @@ impl<'tcx> Analyzer<'tcx> {
         &self, cond: Rc<RExpr<'tcx>>, then_block: Rc<RExpr<'tcx>>,
         else_opt: Option<Rc<RExpr<'tcx>>>, env: &mut Env<'tcx>,
     ) -> Result<String, AnalysisError> {
-        let cond_str = self.expr_to_constraint(cond.clone(), env)?;
+        let (cond_str, matched) = self.if_cond_to_constraint(cond.clone(), env)?;
 
         let mut then_env = env.gen_new_env("then".to_string(), then_block.clone())?;
         then_env.add_assumption(cond_str.clone(), cond.clone());
+        if let (RExprKind::LetBinding { pat, .. }, Some(value)) = (&cond.kind, matched) {
+            self.bind_pattern(pat.clone(), Some(value), &mut then_env)?;
+        }
         let then_value = self.block_to_constraint(then_block, &mut then_env)?;
 
         let else_block = else_opt.expect("Else block of if initializer not found");
         let mut else_env = env.gen_new_env("else".to_string(), else_block.clone())?;
         else_env.add_assumption(format!("(not {})", cond_str.clone()), cond);
-        let else_value = self.block_to_constraint(else_block, &mut else_env)?;
+        let else_value = self.branch_to_constraint(else_block, &mut else_env)?;
+
+        let value = Analyzer::branch_value(
+            cond_str.clone(),
+            (&then_env, then_value),
+            (&else_env, else_value),
+        );
+        env.merge_then_else_env(cond_str, then_env, Some(else_env), &self.symbols)?;
+        Ok(value)
+    }
+
+    /// Condition of an `if`; for `if let`, also the value matched against the pattern
+    pub fn if_cond_to_constraint(
+        &self, cond: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
+    ) -> Result<(String, Option<String>), AnalysisError> {
+        match &cond.kind {
+            RExprKind::LetBinding { expr, pat } => {
+                let value = self.expr_to_constraint(expr.clone(), env)?;
+                Ok((self.pattern_to_cond(pat.clone(), &value)?, Some(value)))
+            }
+            _ => Ok((self.expr_to_constraint(cond, env)?, None)),
+        }
+    }
+
+    /// Value of a match: an ite chain over the arms, the last one taken whenever reached
+    pub fn arms_to_constraint(
+        &self, value: &str, arms: &[Rc<RExpr<'tcx>>], env: &mut Env<'tcx>,
+    ) -> Result<String, AnalysisError> {
+        let Some((arm, rest)) = arms.split_first() else {
+            return Err(AnalysisError::UnsupportedPattern("Match without arms".into()));
+        };
+        let RExprKind::Arm { pattern, body, .. } = &arm.kind else {
+            return Err(AnalysisError::UnsupportedPattern("Unknown arm pattern".into()));
+        };
+        if rest.is_empty() {
+            self.bind_pattern(pattern.clone(), Some(value.to_string()), env)?;
+            return self.branch_to_constraint(body.clone(), env);
+        }
+
+        let cond_str = self.arm_to_cond(arm.clone(), value, env)?;
+        let mut arm_env = env.gen_new_env(format!("arm{}", arms.len()), arm.clone())?;
+        arm_env.add_assumption(cond_str.clone(), arm.clone());
+        self.bind_pattern(pattern.clone(), Some(value.to_string()), &mut arm_env)?;
+        let arm_value = self.branch_to_constraint(body.clone(), &mut arm_env)?;
+
+        let mut rest_env = env.gen_new_env(format!("arms{}", arms.len()), rest[0].clone())?;
+        rest_env.add_assumption(format!("(not {})", cond_str), arm.clone());
+        let rest_value = self.arms_to_constraint(value, rest, &mut rest_env)?;
+
+        let value = Analyzer::branch_value(
+            cond_str.clone(),
+            (&arm_env, arm_value),
+            (&rest_env, rest_value),
+        );
+        env.merge_then_else_env(cond_str, arm_env, Some(rest_env), &self.symbols)?;
+        Ok(value)
+    }
+
+    /// Value of an arm body or an else branch, which need not be a block
+    pub fn branch_to_constraint(
+        &self, body: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
+    ) -> Result<String, AnalysisError> {
+        match body.kind {
+            RExprKind::Block { .. } => self.block_to_constraint(body, env),
+            _ => self.expr_to_constraint(body, env),
+        }
+    }
+
+    /// Condition under which `value` is matched by the arm, guard included
+    pub fn arm_to_cond(
+        &self, arm: Rc<RExpr<'tcx>>, value: &str, env: &Env<'tcx>,
+    ) -> Result<String, AnalysisError> {
+        let RExprKind::Arm { pattern, guard, .. } = &arm.kind else {
+            return Err(AnalysisError::UnsupportedPattern("Unknown arm pattern".into()));
+        };
+        let pattern_cond = self.pattern_to_cond(pattern.clone(), value)?;
+        match guard {
+            Some(guard) => {
+                // The guard sees the bindings as the parts of the value they are bound to
+                let mut guard_env = env.clone();
+                self.alias_pattern(pattern.clone(), value, &mut guard_env)?;
+                let guard_cond = self.expr_to_constraint(guard.clone(), &mut guard_env)?;
+                Ok(Env::conjunction(&[pattern_cond, guard_cond]))
+            }
+            None => Ok(pattern_cond),
+        }
+    }
+
+    /// Condition under which `value` is matched by `pattern`
+    pub fn pattern_to_cond(
+        &self, pattern: Rc<RExpr<'tcx>>, value: &str,
+    ) -> Result<String, AnalysisError> {
+        use RPatKind::*;
+
+        let RExprKind::Pat { kind } = &pattern.kind else {
+            return Err(AnalysisError::UnsupportedPattern("Unknown pattern".into()));
+        };
+        match kind {
+            Wild | Binding { subpattern: None, .. } => Ok("true".to_string()),
+            // A reference is its referent, so `&Some(x)` and matching on `&opt` test the referent
+            Binding { subpattern: Some(subpattern), .. }
+            | AscribeUserType { subpattern, .. }
+            | Deref { subpattern } => self.pattern_to_cond(subpattern.clone(), value),
+            Variant { variant_index, subpatterns, .. } => {
+                let mut conds = vec![variant_test(pattern.ty(), *variant_index, value)];
+                for RFieldPat { field, pattern: subpattern } in subpatterns.iter() {
+                    let accessor = field_accessor(pattern.ty(), *variant_index, *field);
+                    let field_value = format!("({} {})", accessor, value);
+                    conds.push(self.pattern_to_cond(subpattern.clone(), &field_value)?);
+                }
+                Ok(Env::conjunction(&conds))
+            }
+            Leaf { subpatterns } => {
+                let mut conds = Vec::new();
+                for RFieldPat { field, pattern: subpattern } in subpatterns.iter() {
+                    let accessor = field_accessor(pattern.ty(), FIRST_VARIANT, *field);
+                    let field_value = format!("({} {})", accessor, value);
+                    conds.push(self.pattern_to_cond(subpattern.clone(), &field_value)?);
+                }
+                Ok(Env::conjunction(&conds))
+            }
+            Constant { value: constant } => {
+                let constant = self.const_to_constraint(*constant, pattern.ty())?;
+                Ok(format!("(= {} {})", value, constant))
+            }
+            Or { pats } => {
+                let mut conds = Vec::new();
+                for pat in pats.iter() {
+                    conds.push(self.pattern_to_cond(pat.clone(), value)?);
+                }
+                Ok(Env::disjunction(&conds))
+            }
+            Range(range) => {
+                let mut conds = Vec::new();
+                if let PatRangeBoundary::Finite(lo) = range.lo {
+                    let lo = self.const_to_constraint(lo, pattern.ty())?;
+                    conds.push(format!("(<= {} {})", lo, value));
+                }
+                if let PatRangeBoundary::Finite(hi) = range.hi {
+                    let hi = self.const_to_constraint(hi, pattern.ty())?;
+                    let op = match range.end {
+                        RangeEnd::Included => "<=",
+                        RangeEnd::Excluded => "<",
+                    };
+                    conds.push(format!("({} {} {})", op, value, hi));
+                }
+                Ok(Env::conjunction(&conds))
+            }
+            _ => Err(AnalysisError::UnsupportedPatternAt {
+                pattern: format!("{:?}", kind),
+                span: pattern.span,
+            }),
+        }
+    }
 
-        env.merge_then_else_env(cond_str.clone(), then_env, Some(else_env))?;
-        Ok(Analyzer::value_to_ite(cond_str, then_value, else_value))
+    pub fn const_to_constraint(
+        &self, constant: mir::Const<'tcx>, ty: Ty<'tcx>,
+    ) -> Result<String, AnalysisError> {
+        let scalar = constant
+            .try_eval_scalar_int(self.tcx, ParamEnv::reveal_all())
+            .ok_or(AnalysisError::UnsupportedPattern(format!("Constant: {:?}", constant)))?;
+        Analyzer::scalar_int_to_constraint(scalar, ty)
+    }
+
+    /// The length of an array type, declaring it if it is an unknown const-generic param
+    pub fn array_len_to_constraint(
+        &self, ty: Ty<'tcx>, expr: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
+    ) -> Result<String, AnalysisError> {
+        if let TyKind::Array(_, len) = ty.kind() {
+            if let ty::ConstKind::Param(param) = len.kind() {
+                let (symbol, declaration) = const_param_symbol(param.name.as_str());
+                env.add_lir(Lir::new_definition(declaration, expr));
+                return Ok(symbol);
+            }
+        }
+        array_len(ty)
+    }
+
+    pub fn scalar_int_to_constraint(
+        scalar: ScalarInt, ty: Ty<'tcx>,
+    ) -> Result<String, AnalysisError> {
+        match ty.kind() {
+            TyKind::Bool => scalar
+                .try_to_bool()
+                .map(|b| b.to_string())
+                .map_err(|_| AnalysisError::UnsupportedPattern(format!("Bool: {:?}", scalar))),
+            TyKind::Int(_) => {
+                let n = scalar.to_int(scalar.size());
+                Ok(if n < 0 { format!("(- {})", n.unsigned_abs()) } else { n.to_string() })
+            }
+            TyKind::Uint(_) => Ok(scalar.to_uint(scalar.size()).to_string()),
+            _ => Err(AnalysisError::UnsupportedPattern(format!("ty: {}", ty))),
+        }
     }
 
     pub fn value_to_ite(cond_str: String, then_value: String, else_value: String) -> String {
         format!("(ite {} {} {})", cond_str, then_value, else_value)
     }
 
+    /// A branch that returns or otherwise diverges has no value to merge
+    fn branch_value(
+        cond_str: String, (then_env, then_value): (&Env<'tcx>, String),
+        (else_env, else_value): (&Env<'tcx>, String),
+    ) -> String {
+        if then_env.is_unreachable() {
+            else_value
+        } else if else_env.is_unreachable() {
+            then_value
+        } else {
+            Analyzer::value_to_ite(cond_str, then_value, else_value)
+        }
+    }
+
     pub fn block_to_constraint(
         &self, block: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
     ) -> Result<String, AnalysisError> {
         let mut return_value = String::new();
         if let RExpr { kind: RExprKind::Block { stmts, expr }, .. } = block.as_ref() {
-            for stmt in stmts {
-                if let AnalysisType::Return(value) = self.analyze_expr(stmt.clone(), env)? {
-                    return Ok(value.expect("No value with return"));
+            let (stmts, expr) = match expr {
+                Some(expr) if matches!(expr.kind, RExprKind::Loop { .. }) => {
+                    (stmts.iter().chain([expr]).cloned().collect(), None)
                 }
+                _ => (stmts.clone(), expr.clone()),
+            };
+            match self.analyze_stmts(&stmts, env)? {
+                AnalysisType::Return(value) => return Ok(value.unwrap_or_default()),
+                AnalysisType::Other => (),
+                _ => return Ok(return_value),
             }
             if let Some(expr) = expr {
-                return_value = self.expr_to_constraint(expr.clone(), env)?;
+                return_value = self.expr_to_constraint(expr, env)?;
             }
         } else {
             return Err(AnalysisError::UnsupportedPattern("Unknown body pattern".into()));
@@ -1,7 +1,10 @@
 // rustc crates
+use rustc_middle::thir::LocalVarId;
+use rustc_middle::ty::Ty;
 use rustc_span::{def_id::LocalDefId, Span};
 
 // std crates
+use std::collections::HashMap as Map;
 use std::rc::Rc;
 
 // Own crates
@@ -11,6 +14,8 @@ use crate::thir::rthir::*;
 pub enum AnalysisType<'tcx> {
     Return(Option<String>),
     Invariant(Rc<RExpr<'tcx>>),
+    Break,
+    Continue,
     Other,
 }
 
@@ -18,6 +23,59 @@ pub enum AnalysisType<'tcx> {
 pub enum AnalysisError {
     FunctionNotFound(LocalDefId),
     UnsupportedPattern(String),
-    RandFunctions,
+    UnsupportedPatternAt { pattern: String, span: Span },
     VerifyError { span: Span },
+    MisplacedInvariant { span: Span },
+    MisplacedContract { span: Span },
+    UnwindingError { span: Span },
+    BoundsError { span: Span },
+    UnresolvedCall { callee: String, span: Span },
+}
+
+#[derive(Debug)]
+pub enum Verdict {
+    Proved,
+    Bounded { unrolled: bool, unfolded: bool },
+}
+
+/// `requires` and `ensures` calls at the head of a fn body
+#[derive(Debug, Default)]
+pub struct Contract<'tcx> {
+    pub requires: Vec<Rc<RExpr<'tcx>>>,
+    /// Closures taking the result
+    pub ensures: Vec<Rc<RExpr<'tcx>>>,
+}
+
+impl<'tcx> Contract<'tcx> {
+    pub fn is_empty(&self) -> bool { self.requires.is_empty() && self.ensures.is_empty() }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExitKind {
+    Break,
+    Continue,
+    Return,
+}
+
+/// A `break` or `continue` that left the innermost loop body, or a `return` that left the
+/// callee, with the path condition and variable state at that point.
+#[derive(Debug, Clone)]
+pub struct LoopExit<'tcx> {
+    pub kind: ExitKind,
+    pub cond: String,
+    pub var_map: Map<LocalVarId, (String, Ty<'tcx>)>,
+    /// The returned value
+    pub value: Option<String>,
+}
+
+/// What the caller sets aside while a callee is analyzed in its env
+#[derive(Debug)]
+pub struct Caller<'tcx> {
+    pub name: String,
+    pub diverged: Vec<String>,
+    pub returns: Vec<LoopExit<'tcx>>,
+    pub var_map: Map<LocalVarId, (String, Ty<'tcx>)>,
+    pub aliases: Map<LocalVarId, Rc<RExpr<'tcx>>>,
+    /// The caller's variables a closure callee captures by `&mut`, and so may write to
+    pub mut_captures: Vec<LocalVarId>,
 }
@@ -0,0 +1,210 @@
+// rustc crates
+// std crates
+// Own crates
+use crate::analyze::*;
+
+struct Candidate<'tcx> {
+    expr: Rc<RExpr<'tcx>>,
+    text: String,
+}
+
+impl<'tcx> Analyzer<'tcx> {
+    /// Houdini-style inference: start from candidates taken from the loop guard,
+    /// the comparisons in the body and the assertions after the loop,
+    /// then drop the ones that do not hold on entry or are not preserved until a fixpoint.
+    pub fn infer_invariants(
+        &self, body: Rc<RExpr<'tcx>>, expr: Rc<RExpr<'tcx>>, rest: &[Rc<RExpr<'tcx>>],
+        env: &Env<'tcx>,
+    ) -> Result<Vec<Rc<RExpr<'tcx>>>, AnalysisError> {
+        let mut candidates = self.gen_candidates(body.clone(), rest, env);
+
+        candidates.retain(|candidate| {
+            let mut entry_env = env.clone();
+            match self.expr_to_constraint(candidate.expr.clone(), &mut entry_env) {
+                Ok(constraint) => {
+                    entry_env.add_assumption(constraint, candidate.expr.clone());
+                    entry_env.get_assumptions_for_verify().is_ok_and(|smt| self.check(smt))
+                }
+                Err(_) => false,
+            }
+        });
+
+        let inferring = self.inferring.replace(true);
+        let result = self.prune_candidates(&mut candidates, body, expr.clone(), env);
+        self.inferring.set(inferring);
+        result?;
+
+        if !inferring {
+            let span = self.tcx.sess.source_map().span_to_diagnostic_string(expr.span);
+            println!("Inferred invariants for the loop at {}:", span);
+            for candidate in candidates.iter() {
+                println!("    invariant({});", candidate.text);
+            }
+            println!();
+        }
+        Ok(candidates.into_iter().map(|candidate| candidate.expr).collect())
+    }
+
+    fn prune_candidates(
+        &self, candidates: &mut Vec<Candidate<'tcx>>, body: Rc<RExpr<'tcx>>, expr: Rc<RExpr<'tcx>>,
+        env: &Env<'tcx>,
+    ) -> Result<(), AnalysisError> {
+        loop {
+            let mut env = env.clone();
+            self.set_var_map(body.clone(), &mut env)?;
+            for candidate in candidates.iter() {
+                let constraint = self.expr_to_constraint(candidate.expr.clone(), &mut env)?;
+                env.add_assumption(constraint, candidate.expr.clone());
+            }
+            let mut loop_env = env.gen_new_env("loop".to_string(), expr.clone())?;
+            self.analyze_block(body.clone(), &mut loop_env)?;
+
+            let continues = Analyzer::take_continues(&mut loop_env);
+            let len = candidates.len();
+            let mut preserved = Vec::new();
+            for candidate in candidates.drain(..) {
+                let mut holds = true;
+                for continue_exit in continues.iter() {
+                    let inv_env = self.gen_preservation_env(
+                        candidate.expr.clone(),
+                        continue_exit,
+                        &loop_env,
+                    )?;
+                    if !self.check(inv_env.get_assumptions_for_verify()?) {
+                        holds = false;
+                        break;
+                    }
+                }
+                if holds {
+                    preserved.push(candidate);
+                }
+            }
+            *candidates = preserved;
+            if candidates.len() == len {
+                return Ok(());
+            }
+        }
+    }
+
+    fn gen_candidates(
+        &self, body: Rc<RExpr<'tcx>>, rest: &[Rc<RExpr<'tcx>>], env: &Env<'tcx>,
+    ) -> Vec<Candidate<'tcx>> {
+        let mut comparisons = Vec::new();
+        Analyzer::search_comparison(body, &mut comparisons);
+        for stmt in rest.iter() {
+            if let RExprKind::Call { ty, args, .. } = &stmt.kind {
+                if let TyKind::FnDef(def_id, ..) = ty.kind() {
+                    if self.get_fn_info(def_id).join("::") == "t3modules::t3assert" {
+                        Analyzer::search_comparison(args[0].clone(), &mut comparisons);
+                    }
+                }
+            }
+        }
+
+        let mut candidates: Vec<Candidate<'tcx>> = Vec::new();
+        for comparison in comparisons {
+            if let RExprKind::Binary { op, lhs, rhs } = &comparison.kind {
+                if !Analyzer::is_pure_over(comparison.clone(), env) {
+                    continue;
+                }
+                for op in Analyzer::weaken_comparison(*op) {
+                    let kind = RExprKind::Binary { op, lhs: lhs.clone(), rhs: rhs.clone() };
+                    let expr = Rc::new(RExpr::new(kind, comparison.ty, comparison.span));
+                    let text = format!(
+                        "{} {} {}",
+                        self.get_snippet(lhs.clone()),
+                        Analyzer::bin_op_to_str(op),
+                        self.get_snippet(rhs.clone())
+                    );
+                    if !candidates.iter().any(|candidate| candidate.text == text) {
+                        candidates.push(Candidate { expr, text });
+                    }
+                }
+            }
+        }
+        candidates
+    }
+
+    fn search_comparison(expr: Rc<RExpr<'tcx>>, comparisons: &mut Vec<Rc<RExpr<'tcx>>>) {
+        use RExprKind::*;
+
+        match &expr.kind {
+            Binary { op, .. } => {
+                if !Analyzer::weaken_comparison(*op).is_empty() {
+                    comparisons.push(expr.clone());
+                }
+            }
+            LogicalOp { lhs, rhs, .. } => {
+                Analyzer::search_comparison(lhs.clone(), comparisons);
+                Analyzer::search_comparison(rhs.clone(), comparisons);
+            }
+            If { cond, then, else_opt } => {
+                Analyzer::search_comparison(cond.clone(), comparisons);
+                Analyzer::search_comparison(then.clone(), comparisons);
+                if let Some(else_block) = else_opt {
+                    Analyzer::search_comparison(else_block.clone(), comparisons);
+                }
+            }
+            Block { stmts, expr } => {
+                for stmt in stmts.iter().chain(expr.iter()) {
+                    Analyzer::search_comparison(stmt.clone(), comparisons);
+                }
+            }
+            Call { args, .. } => {
+                for arg in args.iter() {
+                    Analyzer::search_comparison(arg.clone(), comparisons);
+                }
+            }
+            LetStmt { initializer: Some(initializer), .. } => {
+                Analyzer::search_comparison(initializer.clone(), comparisons);
+            }
+            Loop { body } => Analyzer::search_comparison(body.clone(), comparisons),
+            _ => (),
+        }
+    }
+
+    /// Candidates may only mention literals and variables already live before the loop
+    fn is_pure_over(expr: Rc<RExpr<'tcx>>, env: &Env<'tcx>) -> bool {
+        use RExprKind::*;
+
+        match &expr.kind {
+            Literal { .. } => true,
+            VarRef { id } => env.var_map.contains_key(id),
+            Unary { arg, .. } => Analyzer::is_pure_over(arg.clone(), env),
+            Binary { lhs, rhs, .. } | LogicalOp { lhs, rhs, .. } => {
+                Analyzer::is_pure_over(lhs.clone(), env) && Analyzer::is_pure_over(rhs.clone(), env)
+            }
+            _ => false,
+        }
+    }
+
+    fn weaken_comparison(op: BinOp) -> Vec<BinOp> {
+        use BinOp::*;
+
+        match op {
+            Lt => vec![Lt, Le],
+            Gt => vec![Gt, Ge],
+            Eq => vec![Eq, Le, Ge],
+            Le | Ge | Ne => vec![op],
+            _ => Vec::new(),
+        }
+    }
+
+    fn bin_op_to_str(op: BinOp) -> &'static str {
+        use BinOp::*;
+
+        match op {
+            Lt => "<",
+            Le => "<=",
+            Gt => ">",
+            Ge => ">=",
+            Eq => "==",
+            Ne => "!=",
+            _ => unreachable!(),
+        }
+    }
+
+    fn get_snippet(&self, expr: Rc<RExpr<'tcx>>) -> String {
+        self.tcx.sess.source_map().span_to_snippet(expr.span).unwrap_or_else(|_| "_".to_string())
+    }
+}
@@ -6,7 +6,7 @@ use rustc_span::Span;
 use std::rc::Rc;
 
 // Own crates
-use crate::analyze::AnalysisError;
+use crate::analyze::{sort::*, AnalysisError};
 use crate::thir::rthir::*;
 
 #[derive(Debug, Clone)]
@@ -24,12 +24,21 @@ impl<'tcx> Lir<'tcx> {
         use LirKind::*;
 
         match &self.kind {
-            Declaration { name, ty } => match ty.kind() {
-                TyKind::Bool => Ok(format!("(declare-const {} Bool)\n", name)),
-                TyKind::Int(_) => Ok(format!("(declare-const {} Int)\n", name)),
-                TyKind::Float(_) => Ok(format!("(declare-const {} Real)\n", name)),
-                _ => Err(AnalysisError::UnsupportedPattern(format!("name: {}, ty: {}", name, ty))),
-            },
+            Declaration { name, ty } => {
+                let mut smt = format!(
+                    "{}(declare-const {} {})\n",
+                    declare_datatypes(*ty)?,
+                    name,
+                    ty_to_sort(*ty)?
+                );
+                // Unsigned integers are Int in SMT, so their range is asserted
+                if let TyKind::Uint(_) = ty.kind() {
+                    smt.push_str(&format!("(assert (<= 0 {}))\n", name));
+                }
+                Ok(smt)
+            }
+            Datatype { ty } => declare_datatypes(*ty),
+            Definition(definition) => Ok(definition.clone()),
             Assert(constraint) => Ok(format!("(assert (not {}))\n", constraint)),
             Assume(constraint) => Ok(format!("(assert {})\n", constraint)),
             Assumptions(constraints) => Ok(constraints.clone()),
@@ -50,6 +59,14 @@ impl<'tcx> Lir<'tcx> {
         Lir::new(LirKind::Declaration { name, ty: ty.clone() }, pat.clone())
     }
 
+    pub fn new_datatype(ty: Ty<'tcx>, expr: Rc<RExpr<'tcx>>) -> Lir<'tcx> {
+        Lir::new(LirKind::Datatype { ty }, expr)
+    }
+
+    pub fn new_definition(definition: String, expr: Rc<RExpr<'tcx>>) -> Lir<'tcx> {
+        Lir::new(LirKind::Definition(definition), expr)
+    }
+
     pub fn new_assert(constraint: String, expr: Rc<RExpr<'tcx>>) -> Lir<'tcx> {
         Lir::new(LirKind::Assert(constraint), expr.clone())
     }
@@ -66,6 +83,8 @@ impl<'tcx> Lir<'tcx> {
 #[derive(Debug, Clone)]
 pub enum LirKind<'tcx> {
     Declaration { name: String, ty: Ty<'tcx> },
+    Datatype { ty: Ty<'tcx> },
+    Definition(String),
     Assert(String),
     Assume(String),
     Assumptions(String),
@@ -0,0 +1,285 @@
+// rustc crates
+use rustc_span::DesugaringKind;
+use rustc_target::abi::{FieldIdx, VariantIdx};
+
+// std crates
+// Own crates
+use crate::analyze::*;
+
+/// Std methods on `Vec<T>` and `[T]`, modeled over SMT sequences
+enum SeqModel {
+    New,
+    Push,
+    Pop,
+    Len,
+    IsEmpty,
+    Swap,
+    Iter,
+    Sum,
+    Index,
+    Deref,
+}
+
+impl SeqModel {
+    fn from_fn_info(fn_info: &[String]) -> Option<SeqModel> {
+        use SeqModel::*;
+
+        let path: Vec<&str> = fn_info.iter().map(String::as_str).collect();
+        match path.as_slice() {
+            ["std", "vec", "Vec", _, "new"] => Some(New),
+            ["std", "vec", "Vec", _, "push"] => Some(Push),
+            ["std", "vec", "Vec", _, "pop"] => Some(Pop),
+            ["std", "vec", "Vec", _, "len"] | ["core", "slice", "<impl [T]>", "len"] => Some(Len),
+            ["std", "vec", "Vec", _, "is_empty"] | ["core", "slice", "<impl [T]>", "is_empty"] => {
+                Some(IsEmpty)
+            }
+            ["core", "slice", "<impl [T]>", "swap"] => Some(Swap),
+            ["core", "slice", "<impl [T]>", "iter"] => Some(Iter),
+            ["std", "iter", "Iterator", "sum"] => Some(Sum),
+            ["std", "ops", "Index", "index"] | ["std", "ops", "IndexMut", "index_mut"] => {
+                Some(Index)
+            }
+            ["std", "ops", "Deref", "deref"] | ["std", "ops", "DerefMut", "deref_mut"] => {
+                Some(Deref)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Std methods on `Option<T>`, modeled over its datatype
+enum OptionModel {
+    /// `unwrap` and `expect`, which panic on `None`
+    Unwrap,
+    IsSome,
+    IsNone,
+}
+
+impl OptionModel {
+    fn from_fn_info(fn_info: &[String]) -> Option<OptionModel> {
+        use OptionModel::*;
+
+        let path: Vec<&str> = fn_info.iter().map(String::as_str).collect();
+        match path.as_slice() {
+            ["core" | "std", "option", "Option", _, "unwrap" | "expect"] => Some(Unwrap),
+            ["core" | "std", "option", "Option", _, "is_some"] => Some(IsSome),
+            ["core" | "std", "option", "Option", _, "is_none"] => Some(IsNone),
+            _ => None,
+        }
+    }
+}
+
+/// Operator traits on primitives, which generic fns call instead of the built-in operators
+fn operator_model(fn_info: &[String]) -> Option<BinOp> {
+    let path: Vec<&str> = fn_info.iter().map(String::as_str).collect();
+    match path.as_slice() {
+        ["std", "ops", "Add", "add"] => Some(BinOp::Add),
+        ["std", "ops", "Sub", "sub"] => Some(BinOp::Sub),
+        ["std", "ops", "Mul", "mul"] => Some(BinOp::Mul),
+        ["std", "ops", "Div", "div"] => Some(BinOp::Div),
+        ["std", "ops", "Rem", "rem"] => Some(BinOp::Rem),
+        ["std", "cmp", "PartialEq", "eq"] => Some(BinOp::Eq),
+        ["std", "cmp", "PartialEq", "ne"] => Some(BinOp::Ne),
+        ["std", "cmp", "PartialOrd", "lt"] => Some(BinOp::Lt),
+        ["std", "cmp", "PartialOrd", "le"] => Some(BinOp::Le),
+        ["std", "cmp", "PartialOrd", "gt"] => Some(BinOp::Gt),
+        ["std", "cmp", "PartialOrd", "ge"] => Some(BinOp::Ge),
+        _ => None,
+    }
+}
+
+impl<'tcx> Analyzer<'tcx> {
+    /// Value of a call to a modeled std method; methods taking `&mut self` also write back
+    pub fn model_to_constraint(
+        &self, fn_info: &[String], args: &[Rc<RExpr<'tcx>>], expr: Rc<RExpr<'tcx>>,
+        env: &mut Env<'tcx>,
+    ) -> Result<String, AnalysisError> {
+        use SeqModel::*;
+
+        if let Some(op) = operator_model(fn_info) {
+            if args[0].ty().peel_refs().is_primitive() {
+                let lhs = self.expr_to_constraint(args[0].clone(), env)?;
+                let rhs = self.expr_to_constraint(args[1].clone(), env)?;
+                return self.bin_op_to_constraint(op, args[0].ty().peel_refs(), &lhs, &rhs);
+            }
+        }
+
+        if let Some(model) = OptionModel::from_fn_info(fn_info) {
+            return self.option_model_to_constraint(model, &args[0], expr, env);
+        }
+
+        let model = self.seq_model(fn_info, args).map_err(|err| {
+            // `for` desugars to `IntoIterator::into_iter` and `Iterator::next` calls
+            if expr.span.is_desugaring(DesugaringKind::ForLoop) {
+                AnalysisError::UnsupportedPatternAt {
+                    pattern: "`for` loop; write it as a `while` loop".into(),
+                    span: expr.span.source_callsite(),
+                }
+            } else {
+                err
+            }
+        })?;
+        if let New = model {
+            env.add_lir(Lir::new_datatype(expr.ty(), expr.clone()));
+            return Ok(format!("(as seq.empty {})", ty_to_sort(expr.ty())?));
+        }
+        let seq = self.borrowed_to_constraint(args[0].clone(), env)?;
+        let len = format!("(seq.len {})", seq);
+        match model {
+            Push => {
+                let value = self.expr_to_constraint(args[1].clone(), env)?;
+                let pushed = format!("(seq.++ {} (seq.unit {}))", seq, value);
+                self.assign_to_place(args[0].clone(), pushed, expr, env)?;
+                Ok(String::new())
+            }
+            Pop => {
+                env.add_lir(Lir::new_datatype(expr.ty(), expr.clone()));
+                let last = format!("(seq.nth {} (- {} 1))", seq, len);
+                let none =
+                    constructor_application(expr.ty(), variant_named(expr.ty(), "None"), &[]);
+                let some =
+                    constructor_application(expr.ty(), variant_named(expr.ty(), "Some"), &[last]);
+                let popped = format!("(seq.extract {} 0 (- {} 1))", seq, len);
+                self.assign_to_place(args[0].clone(), popped, expr, env)?;
+                Ok(format!("(ite (= {} 0) {} {})", len, none, some))
+            }
+            Len => Ok(len),
+            IsEmpty => Ok(format!("(= {} 0)", len)),
+            Swap => {
+                let a = self.expr_to_constraint(args[1].clone(), env)?;
+                let b = self.expr_to_constraint(args[2].clone(), env)?;
+                self.verify_index(&a, &len, expr.clone(), env)?;
+                self.verify_index(&b, &len, expr.clone(), env)?;
+                let nth_a = format!("(seq.nth {} {})", seq, a);
+                let nth_b = format!("(seq.nth {} {})", seq, b);
+                let swapped = seq_store(&seq_store(&seq, &a, &nth_b), &b, &nth_a);
+                self.assign_to_place(args[0].clone(), swapped, expr, env)?;
+                Ok(String::new())
+            }
+            Iter | Deref => Ok(seq),
+            Sum => {
+                let elem_ty = seq_elem_ty(args[0].ty()).expect("Sum over a non-sequence");
+                let (name, definition) = seq_sum(elem_ty)?;
+                env.add_lir(Lir::new_definition(definition, expr.clone()));
+                Ok(format!("({} {})", name, seq))
+            }
+            Index => match self.seq_index(args, &len, expr, env)? {
+                (index, None) => Ok(format!("(seq.nth {} {})", seq, index)),
+                (start, Some(end)) => {
+                    Ok(format!("(seq.extract {} {} (- {} {}))", seq, start, end, start))
+                }
+            },
+            New => unreachable!(),
+        }
+    }
+
+    fn option_model_to_constraint(
+        &self, model: OptionModel, option: &Rc<RExpr<'tcx>>, expr: Rc<RExpr<'tcx>>,
+        env: &mut Env<'tcx>,
+    ) -> Result<String, AnalysisError> {
+        let option_ty = option.ty().peel_refs();
+        env.add_lir(Lir::new_datatype(option_ty, option.clone()));
+        let value = self.expr_to_constraint(option.clone(), env)?;
+        let some = variant_named(option_ty, "Some");
+        let is_some = variant_test(option_ty, some, &value);
+        match model {
+            OptionModel::Unwrap => {
+                env.add_assumption(is_some, expr.clone());
+                self.verify(env.get_assumptions_for_verify()?, env)?;
+                let accessor = field_accessor(option_ty, some, FieldIdx::from_u32(0));
+                Ok(format!("({} {})", accessor, value))
+            }
+            OptionModel::IsSome => Ok(is_some),
+            OptionModel::IsNone => Ok(format!("(not {})", is_some)),
+        }
+    }
+
+    /// Writing through `index_mut` or `deref_mut` writes to the sequence they borrow
+    pub fn model_place_to_update(
+        &self, ty: Ty<'tcx>, args: &[Rc<RExpr<'tcx>>], place: Rc<RExpr<'tcx>>, value: String,
+        env: &mut Env<'tcx>,
+    ) -> Result<(LocalVarId, String), AnalysisError> {
+        let fn_info = match ty.kind() {
+            TyKind::FnDef(def_id, ..) => self.get_fn_info(def_id),
+            _ => Vec::new(),
+        };
+        match self.seq_model(&fn_info, args) {
+            Ok(SeqModel::Index) => {
+                let seq = self.borrowed_to_constraint(args[0].clone(), env)?;
+                let len = format!("(seq.len {})", seq);
+                let stored = match self.seq_index(args, &len, place, env)? {
+                    (index, None) => seq_store(&seq, &index, &value),
+                    (start, Some(end)) => format!(
+                        "(seq.++ (seq.extract {} 0 {}) {} (seq.extract {} {} (- {} {})))",
+                        seq, start, value, seq, end, len, end
+                    ),
+                };
+                self.place_to_update(args[0].clone(), stored, env)
+            }
+            Ok(SeqModel::Deref) => self.place_to_update(args[0].clone(), value, env),
+            _ => Err(AnalysisError::UnsupportedPatternAt {
+                pattern: "Assignment to this place".into(),
+                span: place.span,
+            }),
+        }
+    }
+
+    /// The model of a std method, if it is modeled for the type of its receiver
+    fn seq_model(
+        &self, fn_info: &[String], args: &[Rc<RExpr<'tcx>>],
+    ) -> Result<SeqModel, AnalysisError> {
+        let on_seq = |model: &SeqModel| match model {
+            SeqModel::New => true,
+            _ => args.first().is_some_and(|arg| seq_elem_ty(arg.ty().peel_refs()).is_some()),
+        };
+        SeqModel::from_fn_info(fn_info)
+            .filter(on_seq)
+            .ok_or(AnalysisError::UnsupportedPattern("Unknown function!".into()))
+    }
+
+    /// The index of an `index` call, or the start and end of a range for slicing,
+    /// with its bounds obligation
+    fn seq_index(
+        &self, args: &[Rc<RExpr<'tcx>>], len: &str, expr: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
+    ) -> Result<(String, Option<String>), AnalysisError> {
+        let index = self.expr_to_constraint(args[1].clone(), env)?;
+        let index_ty = args[1].ty();
+        match index_ty.kind() {
+            TyKind::Adt(adt_def, _) => {
+                // Ranges are structs, and a missing bound is the start or end of the sequence
+                let bound = |name: &str| {
+                    let fields = &adt_def.non_enum_variant().fields;
+                    fields.iter_enumerated().find(|(_, field)| field.name.as_str() == name).map(
+                        |(field, _)| {
+                            format!(
+                                "({} {})",
+                                field_accessor(index_ty, FIRST_VARIANT, field),
+                                index
+                            )
+                        },
+                    )
+                };
+                let start = bound("start").unwrap_or("0".to_string());
+                let end = bound("end").unwrap_or(len.to_string());
+                self.verify_range(&start, &end, len, expr, env)?;
+                Ok((start, Some(end)))
+            }
+            _ => {
+                self.verify_index(&index, len, expr, env)?;
+                Ok((index, None))
+            }
+        }
+    }
+}
+
+fn variant_named(ty: Ty, name: &str) -> VariantIdx {
+    let TyKind::Adt(adt_def, _) = ty.kind() else {
+        panic!("Variant {} of a non-ADT", name);
+    };
+    adt_def
+        .variants()
+        .iter_enumerated()
+        .find(|(_, variant)| variant.name.as_str() == name)
+        .map(|(variant_index, _)| variant_index)
+        .expect("Variant not found")
+}
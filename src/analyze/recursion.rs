@@ -0,0 +1,130 @@
+// rustc crates
+// std crates
+// Own crates
+use crate::analyze::*;
+
+impl<'tcx> Analyzer<'tcx> {
+    /// `requires(..)` and `ensures(..)` may be written as the first statements of a fn body
+    pub fn split_contract(&self, body: Rc<RExpr<'tcx>>) -> (Contract<'tcx>, Rc<RExpr<'tcx>>) {
+        let mut contract = Contract::default();
+        let RExprKind::Block { stmts, expr } = &body.kind else {
+            return (contract, body);
+        };
+        let mut count = 0;
+        for stmt in stmts.iter() {
+            let RExprKind::Call { args, .. } = &stmt.kind else {
+                break;
+            };
+            if self.is_t3modules_call(stmt, "requires") {
+                contract.requires.push(args[0].clone());
+            } else if self.is_t3modules_call(stmt, "ensures") {
+                contract.ensures.push(args[0].clone());
+            } else {
+                break;
+            }
+            count += 1;
+        }
+        if contract.is_empty() {
+            return (contract, body);
+        }
+        let kind = RExprKind::Block { stmts: stmts[count..].to_vec(), expr: expr.clone() };
+        (contract, Rc::new(RExpr::new(kind, body.ty, body.span)))
+    }
+
+    /// A fn body is verified against its contract once, with symbolic params.
+    /// The recursive calls in it are assumed to keep the contract.
+    pub fn verify_contract(
+        &self, name: &str, callee: (LocalDefId, GenericArgsRef<'tcx>), params: &[RParam<'tcx>],
+        contract: &Contract<'tcx>, body: Rc<RExpr<'tcx>>,
+    ) -> Result<(), AnalysisError> {
+        // Inference analyzes without verifying, so the contract is left for the analysis after
+        if self.inferring.get() || !self.verified.borrow_mut().insert(callee) {
+            return Ok(());
+        }
+        let mut env = Env::new();
+        env.enter_fn(name);
+        for param in params.iter() {
+            if let Some(pat) = &param.pat {
+                self.bind_pattern(pat.clone(), None, &mut env)?;
+            }
+        }
+        // The contract speaks of the params as passed, which is how calls assume it
+        let entry_vars = env.var_map.clone();
+        for requires in contract.requires.iter() {
+            let constraint = self.expr_to_constraint(requires.clone(), &mut env)?;
+            env.add_assumption(constraint, requires.clone());
+        }
+        let value = self.callee_body_to_constraint(body.clone(), &mut env)?;
+        let value = env.resume_returns(value, body, &self.symbols);
+        env.var_map = entry_vars;
+        for ensures in contract.ensures.iter() {
+            let constraint = self.ensures_to_constraint(ensures.clone(), &value, &mut env)?;
+            env.add_assumption(constraint, ensures.clone());
+            self.verify(env.get_assumptions_for_verify()?, &env)?;
+        }
+        Ok(())
+    }
+
+    /// A call through the contract: `requires` is checked at the call and `ensures` is assumed
+    /// of a fresh result. Places passed by `&mut` may hold anything afterwards.
+    pub fn contract_to_constraint(
+        &self, name: &str, params: &[RParam<'tcx>], contract: &Contract<'tcx>,
+        args: &[Rc<RExpr<'tcx>>], expr: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
+    ) -> Result<String, AnalysisError> {
+        let values = self.args_to_constraint(params, args, env)?;
+        let caller = env.enter_fn(name);
+        let value = self.bind_params(params, values, env).and_then(|()| {
+            for requires in contract.requires.iter() {
+                let constraint = self.expr_to_constraint(requires.clone(), env)?;
+                env.add_assumption(constraint, expr.clone());
+                self.verify(env.get_assumptions_for_verify()?, env)?;
+            }
+            if expr.ty().is_unit() {
+                return Ok(String::new());
+            }
+            let result = self.fresh_constant("result", expr.clone(), env);
+            for ensures in contract.ensures.iter() {
+                let constraint = self.ensures_to_constraint(ensures.clone(), &result, env)?;
+                env.add_assumption(constraint, ensures.clone());
+            }
+            Ok(result)
+        });
+        let write_backs: Vec<_> = self
+            .param_write_backs(params, args, env)
+            .into_iter()
+            .map(|(place, _, arg)| (place.clone(), self.fresh_constant("out", place, env), arg))
+            .collect();
+        env.leave_body(caller);
+        let value = value?;
+        self.write_back_params(write_backs, env)?;
+        Ok(value)
+    }
+
+    /// The body of an `ensures` closure, with its param bound to `result`
+    fn ensures_to_constraint(
+        &self, ensures: Rc<RExpr<'tcx>>, result: &str, env: &mut Env<'tcx>,
+    ) -> Result<String, AnalysisError> {
+        let RExprKind::Closure { closure_id, .. } = &ensures.kind else {
+            return Err(AnalysisError::UnsupportedPatternAt {
+                pattern: "ensures without a closure".into(),
+                span: ensures.span,
+            });
+        };
+        let closure = self.get_fn(*closure_id)?;
+        if let Some(pat) = closure.params.get(1).and_then(|param| param.pat.clone()) {
+            self.bind_pattern(pat, Some(result.to_string()), env)?;
+        }
+        self.branch_to_constraint(closure.body.as_ref().expect("Body not found").clone(), env)
+    }
+
+    /// A recursive call past the unfold bound: its path is cut off and the verdict is bounded
+    pub fn cut_recursion(&self, expr: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>) -> String {
+        env.add_assumption("false".to_string(), expr.clone());
+        self.unfolded.set(true);
+        if expr.ty().is_unit() {
+            String::new()
+        } else {
+            self.fresh_constant("result", expr, env)
+        }
+    }
+}
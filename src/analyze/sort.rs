@@ -0,0 +1,207 @@
+// rustc crates
+use rustc_middle::ty::{self, ParamEnv, Ty, TyCtxt, TyKind};
+use rustc_span::{def_id::DefId, sym};
+use rustc_target::abi::{FieldIdx, VariantIdx, FIRST_VARIANT};
+
+// std crates
+// Own crates
+use crate::analyze::AnalysisError;
+
+/// Tuples, structs and enums are declared as SMT datatypes named after their Rust type,
+/// with one constructor per variant and one accessor per field.
+/// Arrays are SMT arrays from Int, with their length kept in the type.
+/// References have the sort of their referent.
+pub fn ty_to_sort(ty: Ty) -> Result<String, AnalysisError> {
+    if let Some(elem_ty) = seq_elem_ty(ty) {
+        return Ok(format!("(Seq {})", ty_to_sort(elem_ty)?));
+    }
+    match ty.kind() {
+        TyKind::Bool => Ok("Bool".to_string()),
+        TyKind::Int(_) | TyKind::Uint(_) => Ok("Int".to_string()),
+        TyKind::Float(_) => Ok("Real".to_string()),
+        TyKind::Array(elem_ty, _) => Ok(format!("(Array Int {})", ty_to_sort(*elem_ty)?)),
+        TyKind::Ref(_, referent_ty, _) => ty_to_sort(*referent_ty),
+        TyKind::Tuple(tys) if !tys.is_empty() => Ok(format!("|{}|", ty)),
+        TyKind::Adt(adt_def, _) if adt_def.is_struct() || adt_def.is_enum() => {
+            Ok(format!("|{}|", ty))
+        }
+        _ => Err(AnalysisError::UnsupportedPattern(format!("ty: {}", ty))),
+    }
+}
+
+/// Vecs, slices and slice iterators are all SMT sequences of their elements
+pub fn seq_elem_ty(ty: Ty) -> Option<Ty> {
+    match ty.kind() {
+        TyKind::Slice(elem_ty) => Some(*elem_ty),
+        TyKind::Adt(adt_def, args) if is_seq_adt(adt_def.did()) => args.types().next(),
+        _ => None,
+    }
+}
+
+fn is_seq_adt(def_id: DefId) -> bool {
+    ty::tls::with(|tcx| {
+        tcx.is_diagnostic_item(sym::Vec, def_id) || tcx.is_diagnostic_item(sym::SliceIter, def_id)
+    })
+}
+
+/// Declarations of the datatypes `ty` is built from, innermost first
+pub fn declare_datatypes(ty: Ty) -> Result<String, AnalysisError> {
+    if let Some(elem_ty) = seq_elem_ty(ty) {
+        return declare_datatypes(elem_ty);
+    }
+    if let TyKind::Array(inner_ty, _) | TyKind::Ref(_, inner_ty, _) = ty.kind() {
+        return declare_datatypes(*inner_ty);
+    }
+    // Field types of generic ADTs are only known through the global type context
+    ty::tls::with(|tcx| {
+        let ty = tcx.lift(ty).expect("Lift ty failed");
+        let variants = variant_field_tys(tcx, ty);
+        if variants.is_empty() {
+            return Ok(String::new());
+        }
+
+        let mut declarations = String::new();
+        let mut constructors = Vec::new();
+        for (variant_index, field_tys) in variants.into_iter().enumerate() {
+            let variant_index = VariantIdx::from_usize(variant_index);
+            let mut fields = Vec::new();
+            for (index, field_ty) in field_tys.into_iter().enumerate() {
+                if field_ty != ty {
+                    declarations.push_str(&declare_datatypes(field_ty)?);
+                }
+                let accessor = field_accessor(ty, variant_index, FieldIdx::from_usize(index));
+                fields.push(format!("({} {})", accessor, ty_to_sort(field_ty)?));
+            }
+            constructors.push(format!("({} {})", constructor(ty, variant_index), fields.join(" ")));
+        }
+        declarations.push_str(&format!(
+            "(declare-datatypes (({} 0)) (({})))\n",
+            ty_to_sort(ty)?,
+            constructors.join(" ")
+        ));
+        Ok(declarations)
+    })
+}
+
+/// Field types of each variant, or nothing if `ty` is not a datatype
+fn variant_field_tys<'tcx>(tcx: TyCtxt<'tcx>, ty: Ty<'tcx>) -> Vec<Vec<Ty<'tcx>>> {
+    match ty.kind() {
+        TyKind::Tuple(tys) if !tys.is_empty() => vec![tys.to_vec()],
+        TyKind::Adt(adt_def, args) => adt_def
+            .variants()
+            .iter()
+            .map(|variant| variant.fields.iter().map(|field| field.ty(tcx, args)).collect())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+pub fn constructor(ty: Ty, variant_index: VariantIdx) -> String {
+    match ty.kind() {
+        TyKind::Adt(adt_def, _) if adt_def.is_enum() => {
+            format!("|{}::{}|", ty, adt_def.variant(variant_index).name)
+        }
+        _ => format!("|{}::new|", ty),
+    }
+}
+
+/// Enum accessors carry the variant, since field names repeat across variants
+pub fn field_accessor(ty: Ty, variant_index: VariantIdx, field: FieldIdx) -> String {
+    match ty.kind() {
+        TyKind::Adt(adt_def, _) if adt_def.is_enum() => {
+            let variant = adt_def.variant(variant_index);
+            format!("|{}::{}.{}|", ty, variant.name, variant.fields[field].name)
+        }
+        TyKind::Adt(adt_def, _) => {
+            format!("|{}.{}|", ty, adt_def.variant(variant_index).fields[field].name)
+        }
+        _ => format!("|{}.{}|", ty, field.as_usize()),
+    }
+}
+
+/// Tester of the variant in SMT, true iff `value` was built by its constructor
+pub fn variant_test(ty: Ty, variant_index: VariantIdx, value: &str) -> String {
+    format!("((_ is {}) {})", constructor(ty, variant_index), value)
+}
+
+pub fn field_count(ty: Ty) -> Result<usize, AnalysisError> {
+    match ty.kind() {
+        TyKind::Tuple(tys) => Ok(tys.len()),
+        TyKind::Adt(adt_def, _) if adt_def.is_struct() => {
+            Ok(adt_def.non_enum_variant().fields.len())
+        }
+        _ => Err(AnalysisError::UnsupportedPattern(format!("Fields of ty: {}", ty))),
+    }
+}
+
+/// `value` of type `ty` with `field` replaced by `field_value`
+pub fn update_field(
+    ty: Ty, value: &str, field: FieldIdx, field_value: String,
+) -> Result<String, AnalysisError> {
+    let fields: Vec<String> = (0..field_count(ty)?)
+        .map(FieldIdx::from_usize)
+        .map(|index| {
+            if index == field {
+                field_value.clone()
+            } else {
+                format!("({} {})", field_accessor(ty, FIRST_VARIANT, index), value)
+            }
+        })
+        .collect();
+    Ok(constructor_application(ty, FIRST_VARIANT, &fields))
+}
+
+/// Nullary constructors are plain symbols in SMT
+pub fn constructor_application(ty: Ty, variant_index: VariantIdx, fields: &[String]) -> String {
+    if fields.is_empty() {
+        constructor(ty, variant_index)
+    } else {
+        format!("({} {})", constructor(ty, variant_index), fields.join(" "))
+    }
+}
+
+/// Length of an array type, known at compile time
+pub fn array_len(ty: Ty) -> Result<String, AnalysisError> {
+    let TyKind::Array(_, len) = ty.kind() else {
+        return Err(AnalysisError::UnsupportedPattern(format!("Length of ty: {}", ty)));
+    };
+    if let ty::ConstKind::Param(param) = len.kind() {
+        return Ok(const_param_symbol(param.name.as_str()).0);
+    }
+    ty::tls::with(|tcx| {
+        let len = tcx.lift(*len).expect("Lift const failed");
+        len.try_eval_target_usize(tcx, ParamEnv::reveal_all())
+            .map(|len| len.to_string())
+            .ok_or(AnalysisError::UnsupportedPattern(format!("Length of ty: {}", ty)))
+    })
+}
+
+/// Name and declaration of the symbolic constant standing for an unknown const-generic `usize`
+pub fn const_param_symbol(name: &str) -> (String, String) {
+    let symbol = format!("|const {}|", name);
+    let declaration = format!("(declare-fun {} () Int)\n(assert (<= 0 {}))\n", symbol, symbol);
+    (symbol, declaration)
+}
+
+/// Name and definition of the recursive function summing a sequence of `elem_ty`
+pub fn seq_sum(elem_ty: Ty) -> Result<(String, String), AnalysisError> {
+    let sort = ty_to_sort(elem_ty)?;
+    let zero = match sort.as_str() {
+        "Int" => "0",
+        "Real" => "0.0",
+        _ => return Err(AnalysisError::UnsupportedPattern(format!("Sum of ty: {}", elem_ty))),
+    };
+    let name = format!("|seq.sum {}|", sort);
+    let definition = format!(
+        "(define-fun-rec {name} ((s (Seq {sort}))) {sort} (ite (= (seq.len s) 0) {zero} (+ (seq.nth s 0) ({name} (seq.extract s 1 (- (seq.len s) 1))))))\n"
+    );
+    Ok((name, definition))
+}
+
+/// `seq` with the element at `index` replaced by `value`
+pub fn seq_store(seq: &str, index: &str, value: &str) -> String {
+    format!(
+        "(seq.++ (seq.extract {} 0 {}) (seq.unit {}) (seq.extract {} (+ {} 1) (- (seq.len {}) (+ {} 1))))",
+        seq, index, value, seq, index, seq, index
+    )
+}
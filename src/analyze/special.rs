@@ -21,6 +21,27 @@ impl<'tcx> Analyzer<'tcx> {
         Ok(AnalysisType::Other)
     }
 
+    /// The panics of `assert!`, `assert_eq!`, `panic!`, `unreachable!` and the like
+    pub fn is_panic(fn_info: &[String]) -> bool {
+        matches!(
+            fn_info.iter().map(String::as_str).collect::<Vec<_>>().as_slice(),
+            ["core" | "std", "panicking", _] | ["std", "rt", "begin_panic" | "panic_fmt"]
+        )
+    }
+
+    /// A panic may not be reachable, and the code after it is not
+    pub fn panic_to_constraint(
+        &self, expr: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
+    ) -> Result<String, AnalysisError> {
+        env.add_assumption("false".to_string(), expr.clone());
+        let smt = env.get_assumptions_for_verify()?;
+        // Panics are expanded from macros, whose call site is in the verified file
+        self.verify(smt, env)
+            .map_err(|_| AnalysisError::VerifyError { span: expr.span.source_callsite() })?;
+        env.diverged.push("true".to_string());
+        Ok(String::new())
+    }
+
     pub fn analyze_invariant(
         &self, args: Box<[Rc<RExpr<'tcx>>]>,
     ) -> Result<AnalysisType<'tcx>, AnalysisError> {
@@ -4,16 +4,6 @@
 use crate::analyze::*;
 
 impl<'tcx> Analyzer<'tcx> {
-    pub fn analyze_local_fn(
-        &self, rthir: Rc<RThir<'tcx>>, args: Box<[Rc<RExpr<'tcx>>]>, env: &mut Env<'tcx>,
-    ) -> Result<(), AnalysisError> {
-        self.analyze_params(&rthir.params, args, env)?;
-        if let Some(body) = &rthir.body {
-            self.analyze_body((*body).clone(), env)?;
-        }
-        Ok(())
-    }
-
     pub fn analyze_literal(
         &self, expr: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
     ) -> Result<(), AnalysisError> {
@@ -45,7 +35,7 @@ impl<'tcx> Analyzer<'tcx> {
 
         match kind {
             Wild => (),
-            Binding { name, ty, var, .. } => env.add_parameter(name.to_string(), ty, var, pat),
+            Binding { ty, var, .. } => env.add_parameter(self.symbols.fresh(var), ty, var, pat),
             _ => return Err(AnalysisError::UnsupportedPattern(format!("{:?}", kind))),
         }
         Ok(())
@@ -56,20 +46,20 @@ impl<'tcx> Analyzer<'tcx> {
         env: &mut Env<'tcx>,
     ) -> Result<AnalysisType<'tcx>, AnalysisError> {
         match ty.kind() {
-            TyKind::FnDef(def_id, ..) => {
+            TyKind::FnDef(def_id, fn_args) => {
+                let (def_id, fn_args) = self.resolve_fn(*def_id, fn_args, expr.span)?;
+                let def_id = &def_id;
                 let mut fn_info = self.get_fn_info(def_id);
-                if let Some(fun) = self.get_local_fn(def_id) {
-                    let fn_env =
-                        env.gen_new_env(fn_info.pop().expect("fn info not found"), expr)?;
-                    match self.analyze_local_fn(fun, args, env) {
-                        Ok(()) => {
-                            env.merge_env(fn_env);
-                            Ok(AnalysisType::Other)
-                        }
-                        Err(why) => Err(why),
-                    }
+                if let Some(closure_id) = self.called_closure(ty) {
+                    self.closure_to_constraint(closure_id, args, expr, env)?;
+                    Ok(AnalysisType::Other)
+                } else if let Some(fun) = self.get_local_fn(def_id, fn_args) {
+                    let fn_name = fn_info.pop().expect("fn info not found");
+                    let callee = (def_id.expect_local(), fn_args);
+                    self.local_fn_to_constraint(&fn_name, callee, fun, args, expr, env)?;
+                    Ok(AnalysisType::Other)
                 } else {
-                    self.analyze_extern_fn(fn_info, args, env)
+                    self.analyze_extern_fn(fn_info, args, expr, env)
                 }
             }
             _ => panic!("Call has not have FnDef"),
@@ -77,42 +67,200 @@ impl<'tcx> Analyzer<'tcx> {
     }
 
     pub fn analyze_extern_fn(
-        &self, fn_info: Vec<String>, args: Box<[Rc<RExpr<'tcx>>]>, env: &mut Env<'tcx>,
+        &self, fn_info: Vec<String>, args: Box<[Rc<RExpr<'tcx>>]>, expr: Rc<RExpr<'tcx>>,
+        env: &mut Env<'tcx>,
     ) -> Result<AnalysisType<'tcx>, AnalysisError> {
         if fn_info[0] == "t3modules" {
             match fn_info[1].as_str() {
                 "t3assert" => self.analyze_t3assert(args, env),
                 "t3assume" => self.analyze_t3assume(args, env),
                 "invariant" => self.analyze_invariant(args),
-                _ => unreachable!(),
+                "requires" | "ensures" => Err(AnalysisError::MisplacedContract { span: expr.span }),
+                _ => {
+                    self.extern_fn_to_constraint(fn_info, args, expr, env)?;
+                    Ok(AnalysisType::Other)
+                }
             }
         } else {
-            Err(AnalysisError::UnsupportedPattern("Unknown function!".into()))
+            self.extern_fn_to_constraint(fn_info, args, expr, env)?;
+            Ok(AnalysisType::Other)
         }
     }
 
     pub fn analyze_let_stmt(
         &self, pattern: Rc<RExpr<'tcx>>, initializer: Option<Rc<RExpr<'tcx>>>,
-        _: Option<Rc<RExpr<'tcx>>>, env: &mut Env<'tcx>,
-    ) -> Result<(), AnalysisError> {
-        if let RExprKind::Pat { kind: RPatKind::Binding { name, ty, var, .. } } = &pattern.kind {
-            let name = format!("{}_{}", env.name, name);
-            let declaration = Lir::new_parameter(name.clone(), ty.clone(), pattern.clone());
-            env.add_lir(declaration);
-            env.insert_var(var, name.clone(), ty);
-            if let Some(init) = initializer {
-                match self.expr_to_constraint(init.clone(), env) {
-                    Ok(value) => {
-                        env.add_assumption(format!("(= {} {})", name, value), init.clone())
-                    }
-                    Err(err) => match err {
-                        AnalysisError::RandFunctions => {}
-                        _ => return Err(err),
-                    },
+        else_block: Option<Rc<RExpr<'tcx>>>, env: &mut Env<'tcx>,
+    ) -> Result<(), AnalysisError> {
+        // Only call arguments may borrow mutably, since writes are passed back by the call
+        if let Some(RExpr {
+            kind: RExprKind::Borrow { borrow_kind: BorrowKind::Mut { .. }, .. },
+            span,
+            ..
+        }) = initializer.as_deref()
+        {
+            return Err(AnalysisError::UnsupportedPatternAt {
+                pattern: "Mutable borrow held by a local".into(),
+                span: *span,
+            });
+        }
+        // Closures have no value of their own; calls inline their bodies
+        if let Some(RExprKind::Closure { .. }) = initializer.as_ref().map(|init| &init.kind) {
+            return Ok(());
+        }
+        if let (Some(init), Some(else_block)) = (&initializer, else_block) {
+            return self.analyze_let_else(pattern, init.clone(), else_block, env);
+        }
+        let value =
+            initializer.clone().map(|init| self.expr_to_constraint(init, env)).transpose()?;
+        self.bind_pattern(pattern.clone(), value, env)?;
+        if let Some(init) = initializer.filter(Analyzer::is_place) {
+            Analyzer::search_mut_ref_aliases(pattern, init, &mut env.aliases)?;
+        }
+        Ok(())
+    }
+
+    /// The else block runs where the pattern does not match and must leave,
+    /// so the code after it runs where the pattern matched
+    fn analyze_let_else(
+        &self, pattern: Rc<RExpr<'tcx>>, initializer: Rc<RExpr<'tcx>>, else_block: Rc<RExpr<'tcx>>,
+        env: &mut Env<'tcx>,
+    ) -> Result<(), AnalysisError> {
+        let value = self.expr_to_constraint(initializer, env)?;
+        let cond_str = self.pattern_to_cond(pattern.clone(), &value)?;
+
+        let mut else_env = env.gen_new_env("else".to_string(), else_block.clone())?;
+        else_env.add_assumption(format!("(not {})", cond_str), pattern.clone());
+        self.analyze_block(else_block.clone(), &mut else_env)?;
+        if !else_env.is_unreachable() {
+            return Err(AnalysisError::UnsupportedPatternAt {
+                pattern: "let-else block that does not diverge".into(),
+                span: else_block.span,
+            });
+        }
+
+        let then_env = env.gen_new_env("then".to_string(), pattern.clone())?;
+        env.merge_then_else_env(cond_str, then_env, Some(else_env), &self.symbols)?;
+        self.bind_pattern(pattern, Some(value), env)
+    }
+
+    /// `ref mut` bindings in `pattern`, each with the part of `place` it borrows.
+    /// The place is evaluated again at each use, so it may not index by a variable.
+    fn search_mut_ref_aliases(
+        pattern: Rc<RExpr<'tcx>>, place: Rc<RExpr<'tcx>>,
+        aliases: &mut Map<LocalVarId, Rc<RExpr<'tcx>>>,
+    ) -> Result<(), AnalysisError> {
+        let RExprKind::Pat { kind } = &pattern.kind else {
+            return Ok(());
+        };
+        match kind {
+            RPatKind::Binding {
+                mode: BindingMode(ByRef::Yes(Mutability::Mut), _), var, ..
+            } => {
+                if let Some(index) = Analyzer::variable_index(&place) {
+                    return Err(AnalysisError::UnsupportedPatternAt {
+                        pattern: "`ref mut` to an element at a non-constant index".into(),
+                        span: index.span,
+                    });
                 }
+                aliases.insert(*var, place);
+            }
+            RPatKind::AscribeUserType { subpattern, .. } => {
+                Analyzer::search_mut_ref_aliases(subpattern.clone(), place, aliases)?
+            }
+            RPatKind::Leaf { subpatterns } => {
+                for RFieldPat { field, pattern: subpattern } in subpatterns.iter() {
+                    let field_place = RExprKind::Field {
+                        lhs: place.clone(),
+                        variant_index: FIRST_VARIANT,
+                        name: *field,
+                    };
+                    let field_place =
+                        Rc::new(RExpr::new(field_place, Some(subpattern.ty()), subpattern.span));
+                    Analyzer::search_mut_ref_aliases(subpattern.clone(), field_place, aliases)?;
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Declares the variables bound by `pattern`, each equal to its part of `value` if known.
+    /// Every binding is a fresh version, so a shadowing `let`, a local bound again in a loop
+    /// and the params of each call to a fn get symbols of their own.
+    pub fn bind_pattern(
+        &self, pattern: Rc<RExpr<'tcx>>, value: Option<String>, env: &mut Env<'tcx>,
+    ) -> Result<(), AnalysisError> {
+        let mut bindings = Vec::new();
+        Analyzer::search_bindings(pattern, value, &mut bindings)?;
+        for (binding, value) in bindings {
+            if let RExprKind::Pat { kind: RPatKind::Binding { ty, var, .. } } = &binding.kind {
+                let name = self.symbols.fresh(var);
+                env.add_parameter(name.clone(), ty, var, binding.clone());
+                if let Some(value) = value {
+                    env.add_assumption(format!("(= {} {})", name, value), binding.clone());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Maps the variables bound by `pattern` straight to their parts of `value`
+    pub fn alias_pattern(
+        &self, pattern: Rc<RExpr<'tcx>>, value: &str, env: &mut Env<'tcx>,
+    ) -> Result<(), AnalysisError> {
+        let mut bindings = Vec::new();
+        Analyzer::search_bindings(pattern, Some(value.to_string()), &mut bindings)?;
+        for (binding, value) in bindings {
+            if let RExprKind::Pat { kind: RPatKind::Binding { ty, var, .. } } = &binding.kind {
+                env.insert_var(var, value.expect("Value of binding not found"), ty);
+            }
+        }
+        Ok(())
+    }
+
+    /// The binding patterns in `pattern`, each with its part of `value`
+    fn search_bindings(
+        pattern: Rc<RExpr<'tcx>>, value: Option<String>,
+        bindings: &mut Vec<(Rc<RExpr<'tcx>>, Option<String>)>,
+    ) -> Result<(), AnalysisError> {
+        use RPatKind::*;
+
+        let RExprKind::Pat { kind } = &pattern.kind else {
+            return Err(AnalysisError::UnsupportedPattern("Unknown pattern".into()));
+        };
+        match kind {
+            Wild | Constant { .. } | Range(..) => (),
+            Binding { subpattern, .. } => {
+                bindings.push((pattern.clone(), value.clone()));
+                if let Some(subpattern) = subpattern {
+                    Analyzer::search_bindings(subpattern.clone(), value, bindings)?;
+                }
+            }
+            AscribeUserType { subpattern, .. } | Deref { subpattern } => {
+                Analyzer::search_bindings(subpattern.clone(), value, bindings)?;
+            }
+            Variant { variant_index, subpatterns, .. } => {
+                for RFieldPat { field, pattern: subpattern } in subpatterns.iter() {
+                    let accessor = field_accessor(pattern.ty(), *variant_index, *field);
+                    let field_value =
+                        value.as_ref().map(|value| format!("({} {})", accessor, value));
+                    Analyzer::search_bindings(subpattern.clone(), field_value, bindings)?;
+                }
+            }
+            Leaf { subpatterns } => {
+                for RFieldPat { field, pattern: subpattern } in subpatterns.iter() {
+                    let accessor = field_accessor(pattern.ty(), FIRST_VARIANT, *field);
+                    let field_value =
+                        value.as_ref().map(|value| format!("({} {})", accessor, value));
+                    Analyzer::search_bindings(subpattern.clone(), field_value, bindings)?;
+                }
+            }
+            _ => {
+                return Err(AnalysisError::UnsupportedPatternAt {
+                    pattern: format!("{:?}", kind),
+                    span: pattern.span,
+                })
             }
-        } else {
-            unreachable!();
         }
         Ok(())
     }
@@ -122,10 +270,9 @@ impl<'tcx> Analyzer<'tcx> {
         env: &mut Env<'tcx>,
     ) -> Result<(), AnalysisError> {
         let rhs = self.expr_to_constraint(rhs, env)?;
-        let (new_lhs, lhs) = env.assign_new_value(lhs.clone())?;
-        let constraint = self.bin_op_to_constraint(op, &lhs, &rhs)?;
-        env.add_assumption(format!("(= {} {})", new_lhs, constraint), expr);
-        Ok(())
+        let lhs_str = self.expr_to_constraint(lhs.clone(), env)?;
+        let constraint = self.bin_op_to_constraint(op, lhs.ty(), &lhs_str, &rhs)?;
+        self.assign_to_place(lhs, constraint, expr, env)
     }
 
     pub fn analyze_assign(
@@ -133,19 +280,84 @@ impl<'tcx> Analyzer<'tcx> {
         env: &mut Env<'tcx>,
     ) -> Result<(), AnalysisError> {
         let rhs = self.expr_to_constraint(rhs.clone(), env)?;
-        let (new_lhs, _) = env.assign_new_value(lhs.clone())?;
-        env.add_assumption(format!("(= {} {})", new_lhs, rhs), expr.clone());
+        self.assign_to_place(lhs, rhs, expr, env)
+    }
+
+    /// Writing to a place gives its base variable a new version
+    pub fn assign_to_place(
+        &self, place: Rc<RExpr<'tcx>>, value: String, expr: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
+    ) -> Result<(), AnalysisError> {
+        let (var_id, value) = self.place_to_update(place, value, env)?;
+        let (new_var, _) = env.new_var_version(&var_id, expr.clone(), &self.symbols);
+        env.add_assumption(format!("(= {} {})", new_var, value), expr);
         Ok(())
     }
 
+    /// The base variable of `place` and its whole value once `value` is written to `place`
+    pub fn place_to_update(
+        &self, place: Rc<RExpr<'tcx>>, value: String, env: &mut Env<'tcx>,
+    ) -> Result<(LocalVarId, String), AnalysisError> {
+        match &place.kind {
+            RExprKind::VarRef { id } | RExprKind::UpvarRef { var_hir_id: id, .. } => {
+                match env.aliases.get(id).cloned() {
+                    Some(place) => self.place_to_update(place, value, env),
+                    None => Ok((*id, value)),
+                }
+            }
+            RExprKind::Field { lhs, name, .. } => {
+                let lhs_str = self.expr_to_constraint(lhs.clone(), env)?;
+                let lhs_value = update_field(lhs.ty(), &lhs_str, *name, value)?;
+                self.place_to_update(lhs.clone(), lhs_value, env)
+            }
+            RExprKind::Index { lhs, index } => {
+                let lhs_str = self.expr_to_constraint(lhs.clone(), env)?;
+                let index_str = self.expr_to_constraint(index.clone(), env)?;
+                let lhs_value = if seq_elem_ty(lhs.ty()).is_some() {
+                    let len = format!("(seq.len {})", lhs_str);
+                    self.verify_index(&index_str, &len, place.clone(), env)?;
+                    seq_store(&lhs_str, &index_str, &value)
+                } else {
+                    let len = self.array_len_to_constraint(lhs.ty(), place.clone(), env)?;
+                    self.verify_index(&index_str, &len, place.clone(), env)?;
+                    format!("(store {} {} {})", lhs_str, index_str, value)
+                };
+                self.place_to_update(lhs.clone(), lhs_value, env)
+            }
+            RExprKind::Borrow { arg, .. } => self.place_to_update(arg.clone(), value, env),
+            RExprKind::Call { ty, args, .. } => {
+                self.model_place_to_update(*ty, args, place.clone(), value, env)
+            }
+            RExprKind::Deref { arg } => match &arg.kind {
+                // Reborrows such as `&mut *(&mut x)` write to the borrowed place
+                RExprKind::Borrow { arg: borrowed, .. } => {
+                    self.place_to_update(borrowed.clone(), value, env)
+                }
+                _ if matches!(arg.ty().kind(), TyKind::Ref(..)) => {
+                    self.place_to_update(arg.clone(), value, env)
+                }
+                _ => Err(AnalysisError::UnsupportedPatternAt {
+                    pattern: "Assignment through this pointer".into(),
+                    span: place.span,
+                }),
+            },
+            _ => Err(AnalysisError::UnsupportedPatternAt {
+                pattern: "Assignment to this place".into(),
+                span: place.span,
+            }),
+        }
+    }
+
     pub fn analyze_if(
         &self, cond: Rc<RExpr<'tcx>>, then_block: Rc<RExpr<'tcx>>,
         else_opt: Option<Rc<RExpr<'tcx>>>, env: &mut Env<'tcx>,
     ) -> Result<(), AnalysisError> {
-        let cond_str = self.expr_to_constraint(cond.clone(), env)?;
+        let (cond_str, matched) = self.if_cond_to_constraint(cond.clone(), env)?;
 
         let mut then_env = env.gen_new_env("then".to_string(), then_block.clone())?;
         then_env.add_assumption(cond_str.clone(), cond.clone());
+        if let (RExprKind::LetBinding { pat, .. }, Some(value)) = (&cond.kind, matched) {
+            self.bind_pattern(pat.clone(), Some(value), &mut then_env)?;
+        }
         self.analyze_block(then_block, &mut then_env)?;
 
         let mut else_env = None;
@@ -156,38 +368,81 @@ impl<'tcx> Analyzer<'tcx> {
             else_env = Some(else_env_)
         }
 
-        env.merge_then_else_env(cond_str.clone(), then_env, else_env)?;
+        env.merge_then_else_env(cond_str.clone(), then_env, else_env, &self.symbols)?;
         Ok(())
     }
 
+    /// A match is analyzed as a chain of ifs, one per arm
+    pub fn analyze_match(
+        &self, scrutinee: Rc<RExpr<'tcx>>, arms: &[Rc<RExpr<'tcx>>], env: &mut Env<'tcx>,
+    ) -> Result<(), AnalysisError> {
+        let value = self.expr_to_constraint(scrutinee, env)?;
+        self.analyze_arms(&value, arms, env)
+    }
+
+    fn analyze_arms(
+        &self, value: &str, arms: &[Rc<RExpr<'tcx>>], env: &mut Env<'tcx>,
+    ) -> Result<(), AnalysisError> {
+        let Some((arm, rest)) = arms.split_first() else {
+            return Ok(());
+        };
+        let RExprKind::Arm { pattern, body, .. } = &arm.kind else {
+            return Err(AnalysisError::UnsupportedPattern("Unknown arm pattern".into()));
+        };
+        // Matches are exhaustive, so the last arm is taken whenever it is reached
+        if rest.is_empty() {
+            self.bind_pattern(pattern.clone(), Some(value.to_string()), env)?;
+            self.analyze_expr(body.clone(), env)?;
+            return Ok(());
+        }
+
+        let cond_str = self.arm_to_cond(arm.clone(), value, env)?;
+        let mut arm_env = env.gen_new_env(format!("arm{}", arms.len()), arm.clone())?;
+        arm_env.add_assumption(cond_str.clone(), arm.clone());
+        self.bind_pattern(pattern.clone(), Some(value.to_string()), &mut arm_env)?;
+        self.analyze_expr(body.clone(), &mut arm_env)?;
+
+        let mut rest_env = env.gen_new_env(format!("arms{}", arms.len()), rest[0].clone())?;
+        rest_env.add_assumption(format!("(not {})", cond_str), arm.clone());
+        self.analyze_arms(value, rest, &mut rest_env)?;
+
+        env.merge_then_else_env(cond_str, arm_env, Some(rest_env), &self.symbols)
+    }
+
     pub fn analyze_block(
         &self, block: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
-    ) -> Result<(), AnalysisError> {
-        if let RExpr { kind: RExprKind::Block { stmts, //expr
-                                                      .. }, .. } = block.as_ref() {
-            for stmt in stmts {
-                self.analyze_expr(stmt.clone(), env)?;
-            }
-            // if let Some(expr) = expr {
-            //     self.analyze_expr(expr.clone())?;
-            // }
+    ) -> Result<AnalysisType<'tcx>, AnalysisError> {
+        if let RExpr { kind: RExprKind::Block { stmts, expr }, .. } = block.as_ref() {
+            let stmts: Vec<Rc<RExpr<'tcx>>> = stmts.iter().chain(expr.iter()).cloned().collect();
+            self.analyze_stmts(&stmts, env)
         } else {
-            return Err(AnalysisError::UnsupportedPattern("Unknown body pattern".into()));
+            Err(AnalysisError::UnsupportedPattern("Unknown body pattern".into()))
         }
-        Ok(())
     }
 
-    // pub fn analyze_block(&self, block: Rc<RExpr<'tcx>>) -> Result<(), AnalysisError> {
-    //     if let RExpr { kind: RExprKind::Block { stmts, expr }, .. } = &*block {
-    //         for stmt in stmts {
-    //             self.analyze_expr(stmt.clone())?;
-    //         }
-    //         if let Some(expr) = expr {
-    //             self.analyze_expr(expr.clone())?;
-    //         }
-    //     } else {
-    //         return Err(AnalysisError::UnsupportedPattern("Unknown body pattern".into()));
-    //     }
-    //     Ok(())
+    pub fn analyze_stmts(
+        &self, stmts: &[Rc<RExpr<'tcx>>], env: &mut Env<'tcx>,
+    ) -> Result<AnalysisType<'tcx>, AnalysisError> {
+        let mut invariants: Vec<Rc<RExpr<'tcx>>> = Vec::new();
+        for (i, stmt) in stmts.iter().enumerate() {
+            let return_value = if let RExprKind::Loop { .. } = stmt.kind {
+                let invariants = std::mem::take(&mut invariants);
+                self.analyze_loop(invariants, stmt.clone(), &stmts[i + 1..], env)?;
+                AnalysisType::Other
+            } else if !invariants.is_empty() && !self.is_invariant(stmt) {
+                return Err(AnalysisError::MisplacedInvariant { span: invariants[0].span });
+            } else {
+                self.analyze_expr(stmt.clone(), env)?
+            };
+            match return_value {
+                AnalysisType::Invariant(invariant) => invariants.push(invariant),
+                AnalysisType::Other => (),
+                _ => return Ok(return_value),
+            }
+        }
+        if let Some(invariant) = invariants.first() {
+            return Err(AnalysisError::MisplacedInvariant { span: invariant.span });
+        }
+        Ok(AnalysisType::Other)
+    }
 }
-// }
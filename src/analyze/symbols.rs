@@ -0,0 +1,45 @@
+// rustc crates
+use rustc_middle::thir::LocalVarId;
+use rustc_middle::ty::TyCtxt;
+
+// std crates
+use std::cell::RefCell;
+use std::collections::HashMap as Map;
+
+// Own crates
+
+/// SMT symbols of the variables, each defined once.
+/// A variable is named after its Rust name, numbered apart from the other variables of that
+/// name, and each of its definitions is the next version of it: `x@0`, `x@1`, and `x.1@0` for
+/// another variable named `x`. Versions are never reused, across calls and branches alike.
+pub struct Symbols<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    /// The base of each variable's symbols and the number of versions made of it
+    vars: RefCell<Map<LocalVarId, (String, usize)>>,
+    /// The number of variables seen with each Rust name
+    names: RefCell<Map<String, usize>>,
+}
+
+impl<'tcx> Symbols<'tcx> {
+    pub fn new(tcx: TyCtxt<'tcx>) -> Self {
+        Self { tcx, vars: RefCell::new(Map::new()), names: RefCell::new(Map::new()) }
+    }
+
+    /// A symbol for the next definition of `var`
+    pub fn fresh(&self, var: &LocalVarId) -> String {
+        let mut vars = self.vars.borrow_mut();
+        let (base, versions) = vars.entry(*var).or_insert_with(|| (self.base(var), 0));
+        let symbol = format!("{}@{}", base, versions);
+        *versions += 1;
+        symbol
+    }
+
+    fn base(&self, var: &LocalVarId) -> String {
+        let name = self.tcx.hir().name(var.0).to_string();
+        let mut names = self.names.borrow_mut();
+        let seen = names.entry(name.clone()).or_insert(0);
+        let base = if *seen == 0 { name } else { format!("{}.{}", name, seen) };
+        *seen += 1;
+        base
+    }
+}
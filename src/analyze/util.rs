@@ -1,5 +1,7 @@
 // rustc crates
+use rustc_middle::ty::{GenericArgsRef, Instance, InstanceKind, ParamEnv};
 use rustc_span::def_id::{DefId, LocalDefId};
+use rustc_span::Span;
 
 // std crates
 use std::io::Write;
@@ -7,8 +9,50 @@ use std::process::Command;
 
 // Own crates
 use crate::analyze::*;
+use crate::thir::generate_mono_rthir;
 impl<'tcx> Analyzer<'tcx> {
-    pub fn verify(&self, mut smt: String, env: &Env<'tcx>) -> Result<(), AnalysisError> {
+    pub fn verify(&self, smt: String, env: &Env<'tcx>) -> Result<(), AnalysisError> {
+        if self.inferring.get() {
+            return Ok(());
+        }
+        if !self.check(smt) {
+            return Err(AnalysisError::VerifyError { span: env.get_latest_span() });
+        }
+
+        if self.is_bounded() {
+            println!("Verification success! (bounded)\n");
+        } else {
+            println!("Verification success!\n");
+        }
+
+        Ok(())
+    }
+
+    /// Obligation `0 <= index < len` at `expr`, assumed from there on
+    pub fn verify_index(
+        &self, index: &str, len: &str, expr: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
+    ) -> Result<(), AnalysisError> {
+        let cond = format!("(and (<= 0 {}) (< {} {}))", index, index, len);
+        self.verify_bounds(cond, expr, env)
+    }
+
+    /// Obligation `0 <= start <= end <= len` at `expr`, assumed from there on
+    pub fn verify_range(
+        &self, start: &str, end: &str, len: &str, expr: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
+    ) -> Result<(), AnalysisError> {
+        let cond = format!("(and (<= 0 {}) (<= {} {}) (<= {} {}))", start, start, end, end, len);
+        self.verify_bounds(cond, expr, env)
+    }
+
+    fn verify_bounds(
+        &self, cond: String, expr: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>,
+    ) -> Result<(), AnalysisError> {
+        env.add_assumption(cond, expr.clone());
+        let smt = env.get_assumptions_for_verify()?;
+        self.verify(smt, env).map_err(|_| AnalysisError::BoundsError { span: expr.span })
+    }
+
+    pub fn check(&self, mut smt: String) -> bool {
         let mut child = Command::new("z3")
             .args(["-in", "-model"])
             .stdin(std::process::Stdio::piped())
@@ -17,6 +61,7 @@ impl<'tcx> Analyzer<'tcx> {
             .expect("Run z3 failed");
 
         let mut stdin = child.stdin.take().expect("Open std failed");
+        smt = Analyzer::dedup_declarations(&smt);
         smt += "(check-sat)\n";
         println!("{}", smt);
         stdin.write_all(smt.as_bytes()).expect("Write smt failed");
@@ -24,25 +69,125 @@ impl<'tcx> Analyzer<'tcx> {
 
         let output = child.wait_with_output().expect("Get stdout failed");
         let result = String::from_utf8(output.stdout).expect("Load result failed");
-        if &result != "unsat\n" {
-            return Err(AnalysisError::VerifyError { span: env.get_latest_span() });
-        }
-
-        println!("Verification success!\n");
+        &result == "unsat\n"
+    }
 
-        Ok(())
+    /// Datatypes and functions are declared wherever they are used, but z3 accepts them only once
+    fn dedup_declarations(smt: &str) -> String {
+        let mut declarations = Vec::new();
+        let mut deduped = String::new();
+        for line in smt.lines() {
+            if line.starts_with("(declare-datatypes")
+                || line.starts_with("(define-fun-rec")
+                || line.starts_with("(declare-fun")
+            {
+                if declarations.contains(&line) {
+                    continue;
+                }
+                declarations.push(line);
+            }
+            deduped.push_str(line);
+            deduped.push('\n');
+        }
+        deduped
     }
 
     pub fn get_fn(&self, fn_id: LocalDefId) -> Result<Rc<RThir<'tcx>>, AnalysisError> {
         self.fn_map.get(&fn_id).cloned().ok_or(AnalysisError::FunctionNotFound(fn_id))
     }
 
-    pub fn get_local_fn(&self, def_id: &DefId) -> Option<Rc<RThir<'tcx>>> {
-        if def_id.is_local() {
-            Some(self.fn_map.get(&def_id.expect_local()).expect("Get local fn failed").clone())
-        } else {
-            None
+    /// The body of a local fn, monomorphized for `args` if it is generic
+    pub fn get_local_fn(
+        &self, def_id: &DefId, args: GenericArgsRef<'tcx>,
+    ) -> Option<Rc<RThir<'tcx>>> {
+        let fn_id = def_id.as_local()?;
+        if args.types().next().is_none() && args.consts().next().is_none() {
+            return Some(self.fn_map.get(&fn_id).expect("Get local fn failed").clone());
+        }
+        let fun = self
+            .mono_map
+            .borrow_mut()
+            .entry((fn_id, args))
+            .or_insert_with(|| {
+                Rc::new(
+                    generate_mono_rthir(&self.tcx, fn_id, args)
+                        .expect("Generate ReducedTHIR failed"),
+                )
+            })
+            .clone();
+        Some(fun)
+    }
+
+    /// The function a call runs and its generic args: trait methods are resolved to the local
+    /// impl, or to the trait's default method, for the call's generic args. Std impls are kept
+    /// as the trait method, which is what the models match on. A call on a `dyn` receiver has no
+    /// impl to choose.
+    pub fn resolve_fn(
+        &self, def_id: DefId, args: GenericArgsRef<'tcx>, span: Span,
+    ) -> Result<(DefId, GenericArgsRef<'tcx>), AnalysisError> {
+        if self.tcx.trait_of_item(def_id).is_none() {
+            return Ok((def_id, args));
+        }
+        match Instance::resolve(self.tcx, ParamEnv::reveal_all(), def_id, args) {
+            Ok(Some(instance)) if matches!(instance.def, InstanceKind::Virtual(..)) => {
+                Err(AnalysisError::UnresolvedCall {
+                    callee: self.tcx.def_path_str_with_args(def_id, args),
+                    span,
+                })
+            }
+            Ok(Some(instance)) if instance.def_id().is_local() => {
+                Ok((instance.def_id(), instance.args))
+            }
+            Ok(Some(_)) => Ok((def_id, args)),
+            _ => Err(AnalysisError::UnresolvedCall {
+                callee: self.tcx.def_path_str_with_args(def_id, args),
+                span,
+            }),
+        }
+    }
+
+    /// The local closure called through `Fn::call`, `FnMut::call_mut` or `FnOnce::call_once`
+    pub fn called_closure(&self, ty: Ty<'tcx>) -> Option<LocalDefId> {
+        let TyKind::FnDef(def_id, args) = ty.kind() else {
+            return None;
+        };
+        let trait_id = self.tcx.trait_of_item(*def_id)?;
+        if !self.tcx.is_fn_trait(trait_id) {
+            return None;
         }
+        match args.type_at(0).kind() {
+            TyKind::Closure(closure_id, _) => closure_id.as_local(),
+            _ => None,
+        }
+    }
+
+    /// Tuple structs and tuple-like variants such as `Some` are built by calling their ctor
+    pub fn is_ctor(&self, ty: Ty<'tcx>) -> bool {
+        match ty.kind() {
+            TyKind::FnDef(def_id, ..) => {
+                matches!(
+                    self.tcx.def_kind(def_id),
+                    DefKind::Ctor(CtorOf::Struct | CtorOf::Variant, _)
+                )
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether some path was cut off by an unroll or unfold bound
+    pub fn is_bounded(&self) -> bool { self.unrolled.get() || self.unfolded.get() }
+
+    /// A number no symbol made so far has used
+    pub fn fresh_id(&self) -> usize {
+        self.fresh.set(self.fresh.get() + 1);
+        self.fresh.get()
+    }
+
+    /// An unconstrained constant of the type of `expr`
+    pub fn fresh_constant(&self, name: &str, expr: Rc<RExpr<'tcx>>, env: &mut Env<'tcx>) -> String {
+        let symbol = format!("{}_{}{}", env.name, name, self.fresh_id());
+        env.add_lir(Lir::new_parameter(symbol.clone(), expr.ty(), expr));
+        symbol
     }
 
     pub fn get_fn_info(&self, def_id: &DefId) -> Vec<String> {
@@ -7,33 +7,77 @@ use std::fs;
 use std::path::Path;
 
 // Own crates
-use crate::analyze::{analyze, AnalysisError};
-use crate::run::FILE;
+use crate::analyze::{analyze, AnalysisError, Verdict};
+use crate::run::{DEFAULT_UNFOLD, FILE, UNFOLD, UNROLL};
 use crate::util::get_fn_map;
 use ariadne::{ColorGenerator, Label, Report, ReportKind, Source};
 
 pub fn drive_tautrust(tcx: TyCtxt) {
     if let Some((main_id, ..)) = tcx.entry_fn(()) {
         let fn_map = get_fn_map(&tcx);
-        if let Err(error) = analyze(main_id.expect_local(), fn_map, tcx) {
-            use AnalysisError::*;
-            match error {
-                FunctionNotFound(id) => eprintln!("Function not found: {:?}", id),
-                UnsupportedPattern(pattern) => eprintln!("Unsupported pattern: {}", pattern),
-                VerifyError { span } => {
-                    print_error(span);
+        match analyze(main_id.expect_local(), fn_map, tcx) {
+            Ok(Verdict::Proved) => println!("All verification success!"),
+            Ok(Verdict::Bounded { unrolled, unfolded }) => {
+                let mut bounds = Vec::new();
+                if unrolled {
+                    bounds.push(format!(
+                        "loops unrolled {} times",
+                        UNROLL.get().expect("No unroll bound")
+                    ));
+                }
+                if unfolded {
+                    bounds.push(format!(
+                        "recursion unfolded {} times",
+                        UNFOLD.get().unwrap_or(&DEFAULT_UNFOLD)
+                    ));
+                }
+                println!("All verification success! (bounded: {})", bounds.join(", "))
+            }
+            Err(error) => {
+                use AnalysisError::*;
+                match error {
+                    FunctionNotFound(id) => eprintln!("Function not found: {:?}", id),
+                    UnsupportedPattern(pattern) => eprintln!("Unsupported pattern: {}", pattern),
+                    UnsupportedPatternAt { pattern, span } => {
+                        print_error(span, "Unsupported pattern", &pattern);
+                    }
+                    VerifyError { span } => {
+                        print_error(span, "Verification Error", "The condition is not satisfied");
+                    }
+                    MisplacedInvariant { span } => print_error(
+                        span,
+                        "Misplaced invariant",
+                        "Invariants must be placed right before a loop or at the head of its body",
+                    ),
+                    MisplacedContract { span } => print_error(
+                        span,
+                        "Misplaced contract",
+                        "Contracts must be placed at the head of a fn body",
+                    ),
+                    UnwindingError { span } => print_error(
+                        span,
+                        "Unwinding assertion failed",
+                        "The loop may not exit within the unroll bound",
+                    ),
+                    BoundsError { span } => print_error(
+                        span,
+                        "Index out of bounds",
+                        "The index may not be less than the length",
+                    ),
+                    UnresolvedCall { callee, span } => print_error(
+                        span,
+                        "Unresolved call",
+                        &format!("No impl of `{}` could be chosen for this call", callee),
+                    ),
                 }
-                _ => unreachable!(),
             }
-        } else {
-            println!("All verification success!")
         }
     } else {
         panic!("No main function!")
     }
 }
 
-fn print_error(span: Span) {
+fn print_error(span: Span, message: &str, label: &str) {
     let mut colors = ColorGenerator::new();
 
     let file = FILE.get().expect("No file given");
@@ -41,10 +85,10 @@ fn print_error(span: Span) {
     let SpanData { lo, hi, .. } = span.data();
     Report::build(ReportKind::Error, format!("{:?}", span).as_str(), 0)
         .with_code(1)
-        .with_message(format!("Verification Error"))
+        .with_message(message)
         .with_label(
             Label::new((file.as_str(), lo.0 as usize..hi.0 as usize))
-                .with_message(format!("The condition is not satisfied"))
+                .with_message(label)
                 .with_color(colors.next()),
         )
         .finish()
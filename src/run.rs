@@ -14,6 +14,10 @@ use std::sync::OnceLock;
 use crate::drive::drive_tautrust;
 
 pub static FILE: OnceLock<String> = OnceLock::new();
+pub static UNROLL: OnceLock<usize> = OnceLock::new();
+pub static UNFOLD: OnceLock<usize> = OnceLock::new();
+pub const DEFAULT_UNFOLD: usize = 3;
+pub static NON_STRICT: OnceLock<()> = OnceLock::new();
 
 struct MyCallbacks {}
 
@@ -21,7 +25,8 @@ impl Callbacks for MyCallbacks {
     fn config(&mut self, config: &mut Config) {
         let opts = &mut config.opts;
         opts.optimize = OptLevel::Aggressive;
-        opts.debug_assertions = false;
+        // `debug_assert!` is verified like `assert!`
+        opts.debug_assertions = true;
     }
 
     // Stop the compilation after handling hir
@@ -41,6 +46,15 @@ pub fn run_tautrust() {
     let mut args_iter = get_args();
     while let Some(arg) = args_iter.next() {
         match arg.as_str() {
+            "--unroll" => {
+                let bound = args_iter.next().expect("No bound given for --unroll");
+                UNROLL.set(bound.parse().expect("Bound of --unroll must be a number")).unwrap();
+            }
+            "--unfold" => {
+                let bound = args_iter.next().expect("No bound given for --unfold");
+                UNFOLD.set(bound.parse().expect("Bound of --unfold must be a number")).unwrap();
+            }
+            "--non-strict" => NON_STRICT.set(()).unwrap(),
             _ => args.push(arg),
         };
     }
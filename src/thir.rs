@@ -1,6 +1,6 @@
 // rustc crates
 use rustc_errors::ErrorGuaranteed;
-use rustc_middle::ty::TyCtxt;
+use rustc_middle::ty::{GenericArgsRef, TyCtxt};
 use rustc_span::def_id::LocalDefId;
 
 // std crates
@@ -26,7 +26,16 @@ pub fn thir_tree(tcx: &TyCtxt<'_>, owner_def: LocalDefId) -> String {
 pub fn generate_rthir<'tcx>(
     tcx: &TyCtxt<'tcx>, owner_def: LocalDefId,
 ) -> Result<RThir<'tcx>, ErrorGuaranteed> {
+    // Borrowed rather than stolen, so generic fns can be reduced again per instantiation
     let (thir, _) = tcx.thir_body(owner_def)?;
-    let thir = thir.steal();
+    let thir = thir.borrow().clone();
     Ok(reduce_thir(thir))
 }
+
+pub fn generate_mono_rthir<'tcx>(
+    tcx: &TyCtxt<'tcx>, owner_def: LocalDefId, args: GenericArgsRef<'tcx>,
+) -> Result<RThir<'tcx>, ErrorGuaranteed> {
+    let (thir, _) = tcx.thir_body(owner_def)?;
+    let thir = thir.borrow().clone();
+    Ok(reduce_thir_with_args(thir, *tcx, args))
+}
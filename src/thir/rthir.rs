@@ -1,12 +1,12 @@
 // rustc crates
 use rustc_hir as hir;
-use rustc_hir::def_id::DefId;
+use rustc_hir::def_id::{DefId, LocalDefId};
 use rustc_hir::BindingMode;
 use rustc_middle::middle::region;
-use rustc_middle::mir::{BinOp, BorrowKind, UnOp};
+use rustc_middle::mir::{self, BinOp, BorrowKind, UnOp};
 use rustc_middle::thir::*;
 use rustc_middle::ty::adjustment::PointerCoercion;
-use rustc_middle::ty::{self, CanonicalUserType, GenericArgsRef, Ty};
+use rustc_middle::ty::{self, AdtDef, CanonicalUserType, GenericArgsRef, Ty};
 use rustc_span::{Span, Symbol};
 use rustc_target::abi::{FieldIdx, VariantIdx};
 
@@ -142,6 +142,35 @@ impl<'a, 'tcx> RThirFormatter<'a, 'tcx> {
                 self.format_expr(subpattern, depth_lvl + 2);
                 self.add_indented_string("}", depth_lvl + 1);
             }
+            RPatKind::Variant { adt_def, args, variant_index, subpatterns } => {
+                self.add_indented_string("Variant {", depth_lvl + 1);
+                self.add_indented_string(&format!("adt_def: {:?}", adt_def), depth_lvl + 2);
+                self.add_indented_string(&format!("args: {:?}", args), depth_lvl + 2);
+                self.add_indented_string(
+                    &format!("variant_index: {:?}", variant_index),
+                    depth_lvl + 2,
+                );
+                self.add_indented_string("subpatterns: [", depth_lvl + 2);
+                for RFieldPat { field, pattern } in subpatterns.iter() {
+                    self.add_indented_string(&format!("field: {:?}", field), depth_lvl + 3);
+                    self.format_expr(pattern, depth_lvl + 3);
+                }
+                self.add_indented_string("]", depth_lvl + 2);
+                self.add_indented_string("}", depth_lvl + 1);
+            }
+            RPatKind::Leaf { subpatterns } => {
+                self.add_indented_string("Leaf {", depth_lvl + 1);
+                self.add_indented_string("subpatterns: [", depth_lvl + 2);
+                for RFieldPat { field, pattern } in subpatterns.iter() {
+                    self.add_indented_string(&format!("field: {:?}", field), depth_lvl + 3);
+                    self.format_expr(pattern, depth_lvl + 3);
+                }
+                self.add_indented_string("]", depth_lvl + 2);
+                self.add_indented_string("}", depth_lvl + 1);
+            }
+            RPatKind::Constant { value } => {
+                self.add_indented_string(&format!("Constant ( {:?} )", value), depth_lvl + 1);
+            }
             RPatKind::Range(pat_range) => {
                 self.add_indented_string(&format!("Range ( {:?} )", pat_range), depth_lvl + 1);
             }
@@ -160,9 +189,10 @@ impl<'a, 'tcx> RThirFormatter<'a, 'tcx> {
     }
 
     fn format_expr(&mut self, expr: &Rc<RExpr<'tcx>>, depth_lvl: usize) {
-        let RExpr { span, kind } = &**expr;
+        let RExpr { span, kind, ty } = &**expr;
         self.add_indented_string("Expr {", depth_lvl);
         self.add_indented_string(&format!("span: {:?}", span), depth_lvl + 1);
+        self.add_indented_string(&format!("ty: {:?}", ty), depth_lvl + 1);
         self.add_indented_string("kind:", depth_lvl + 1);
         self.format_expr_kind(&kind, depth_lvl + 2);
         self.add_indented_string("}", depth_lvl);
@@ -321,6 +351,26 @@ impl<'a, 'tcx> RThirFormatter<'a, 'tcx> {
                 self.format_expr(rhs, depth_lvl + 2);
                 self.add_indented_string("}", depth_lvl);
             }
+            Adt { adt_def, variant_index, args, fields, base } => {
+                self.add_indented_string("Adt {", depth_lvl);
+                self.add_indented_string(&format!("adt_def: {:?}", adt_def), depth_lvl + 1);
+                self.add_indented_string(
+                    &format!("variant_index: {:?}", variant_index),
+                    depth_lvl + 1,
+                );
+                self.add_indented_string(&format!("args: {:?}", args), depth_lvl + 1);
+                self.add_indented_string("fields: [", depth_lvl + 1);
+                for RFieldExpr { name, expr } in fields.iter() {
+                    self.add_indented_string(&format!("name: {:?}", name), depth_lvl + 2);
+                    self.format_expr(expr, depth_lvl + 2);
+                }
+                self.add_indented_string("]", depth_lvl + 1);
+                if let Some(base) = base {
+                    self.add_indented_string("base:", depth_lvl + 1);
+                    self.format_expr(base, depth_lvl + 2);
+                }
+                self.add_indented_string("}", depth_lvl);
+            }
             Field { lhs, variant_index, name } => {
                 self.add_indented_string("Field {", depth_lvl);
                 self.add_indented_string(
@@ -411,6 +461,16 @@ impl<'a, 'tcx> RThirFormatter<'a, 'tcx> {
                 self.add_indented_string("]", depth_lvl + 1);
                 self.add_indented_string("}", depth_lvl);
             }
+            Closure { closure_id, upvars } => {
+                self.add_indented_string("Closure {", depth_lvl);
+                self.add_indented_string(&format!("closure_id: {:?}", closure_id), depth_lvl + 1);
+                self.add_indented_string("upvars: [", depth_lvl + 1);
+                for upvar in upvars.iter() {
+                    self.format_expr(upvar, depth_lvl + 2);
+                }
+                self.add_indented_string("]", depth_lvl + 1);
+                self.add_indented_string("}", depth_lvl);
+            }
             PlaceTypeAscription { source, user_ty } => {
                 self.add_indented_string("PlaceTypeAscription {", depth_lvl);
                 self.add_indented_string(&format!("user_ty: {:?}", user_ty), depth_lvl + 1);
@@ -533,6 +593,21 @@ pub enum RPatKind<'tcx> {
         mutability: hir::Mutability,
     },
 
+    Variant {
+        adt_def: AdtDef<'tcx>,
+        args: GenericArgsRef<'tcx>,
+        variant_index: VariantIdx,
+        subpatterns: Vec<RFieldPat<'tcx>>,
+    },
+
+    Leaf {
+        subpatterns: Vec<RFieldPat<'tcx>>,
+    },
+
+    Constant {
+        value: mir::Const<'tcx>,
+    },
+
     Range(Box<PatRange<'tcx>>),
 
     Or {
@@ -540,14 +615,31 @@ pub enum RPatKind<'tcx> {
     },
 }
 
+#[derive(Clone, Debug)]
+pub struct RFieldExpr<'tcx> {
+    pub name: FieldIdx,
+    pub expr: Rc<RExpr<'tcx>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RFieldPat<'tcx> {
+    pub field: FieldIdx,
+    pub pattern: Rc<RExpr<'tcx>>,
+}
+
 #[derive(Clone, Debug)]
 pub struct RExpr<'tcx> {
     pub kind: RExprKind<'tcx>,
+    pub ty: Option<Ty<'tcx>>,
     pub span: Span,
 }
 
 impl<'tcx> RExpr<'tcx> {
-    pub fn new(kind: RExprKind<'tcx>, span: Span) -> Self { Self { kind, span } }
+    pub fn new(kind: RExprKind<'tcx>, ty: Option<Ty<'tcx>>, span: Span) -> Self {
+        Self { kind, ty, span }
+    }
+
+    pub fn ty(&self) -> Ty<'tcx> { self.ty.expect("Type of expr not found") }
 }
 
 type UserTy<'tcx> = Option<Box<CanonicalUserType<'tcx>>>;
@@ -617,6 +709,13 @@ pub enum RExprKind<'tcx> {
         lhs: Rc<RExpr<'tcx>>,
         rhs: Rc<RExpr<'tcx>>,
     },
+    Adt {
+        adt_def: AdtDef<'tcx>,
+        variant_index: VariantIdx,
+        args: GenericArgsRef<'tcx>,
+        fields: Box<[RFieldExpr<'tcx>]>,
+        base: Option<Rc<RExpr<'tcx>>>,
+    },
     Field {
         lhs: Rc<RExpr<'tcx>>,
         variant_index: VariantIdx,
@@ -657,6 +756,10 @@ pub enum RExprKind<'tcx> {
     Tuple {
         fields: Box<[Rc<RExpr<'tcx>>]>,
     },
+    Closure {
+        closure_id: LocalDefId,
+        upvars: Box<[Rc<RExpr<'tcx>>]>,
+    },
     PlaceTypeAscription {
         source: Rc<RExpr<'tcx>>,
         user_ty: UserTy<'tcx>,
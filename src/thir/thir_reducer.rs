@@ -1,5 +1,6 @@
 // rustc crates
 use rustc_middle::thir::*;
+use rustc_middle::ty::{EarlyBinder, GenericArgsRef, ParamEnv, Ty, TyCtxt, TypeFoldable};
 use rustc_span::Span;
 
 // std crates
@@ -9,19 +10,41 @@ use std::rc::Rc;
 use crate::thir::rthir::*;
 
 pub fn reduce_thir(thir: Thir) -> RThir {
-    let mut reducer = ThirReducer::new(thir);
+    let mut reducer = ThirReducer::new(thir, None);
+    reducer.reduce();
+    reducer.reduced_thir
+}
+
+/// Reduces the body of a generic fn with `args` substituted into its types
+pub fn reduce_thir_with_args<'tcx>(
+    thir: Thir<'tcx>, tcx: TyCtxt<'tcx>, args: GenericArgsRef<'tcx>,
+) -> RThir<'tcx> {
+    let mut reducer = ThirReducer::new(thir, Some((tcx, args)));
     reducer.reduce();
     reducer.reduced_thir
 }
 
-#[derive(Debug)]
 struct ThirReducer<'tcx> {
     thir: Thir<'tcx>,
     reduced_thir: RThir<'tcx>,
+    instance: Option<(TyCtxt<'tcx>, GenericArgsRef<'tcx>)>,
 }
 
 impl<'tcx> ThirReducer<'tcx> {
-    fn new(thir: Thir<'tcx>) -> Self { Self { thir, reduced_thir: RThir::new() } }
+    fn new(thir: Thir<'tcx>, instance: Option<(TyCtxt<'tcx>, GenericArgsRef<'tcx>)>) -> Self {
+        Self { thir, reduced_thir: RThir::new(), instance }
+    }
+
+    fn instantiate<T: TypeFoldable<TyCtxt<'tcx>>>(&self, value: T) -> T {
+        match self.instance {
+            Some((tcx, args)) => tcx.instantiate_and_normalize_erasing_regions(
+                args,
+                ParamEnv::reveal_all(),
+                EarlyBinder::bind(value),
+            ),
+            None => value,
+        }
+    }
 
     fn reduce(&mut self) {
         let new_params = self.reduce_params();
@@ -44,8 +67,12 @@ impl<'tcx> ThirReducer<'tcx> {
     }
 
     fn reduce_pattern(&self, pat: &Box<Pat<'tcx>>) -> Rc<RExpr<'tcx>> {
-        let Pat { span, kind, .. } = &**pat;
-        Rc::new(RExpr::new(RExprKind::Pat { kind: self.reduce_pattern_kind(kind) }, *span))
+        let Pat { span, kind, ty } = &**pat;
+        Rc::new(RExpr::new(
+            RExprKind::Pat { kind: self.reduce_pattern_kind(kind) },
+            Some(self.instantiate(*ty)),
+            *span,
+        ))
     }
 
     fn reduce_pattern_kind(&self, pat_kind: &PatKind<'tcx>) -> RPatKind<'tcx> {
@@ -67,7 +94,7 @@ impl<'tcx> ThirReducer<'tcx> {
                 name: *name,
                 mode: *mode,
                 var: *var,
-                ty: *ty,
+                ty: self.instantiate(*ty),
                 subpattern: if let Some(pat) = subpattern {
                     Some(self.reduce_pattern(pat))
                 } else {
@@ -82,6 +109,28 @@ impl<'tcx> ThirReducer<'tcx> {
                 subpattern: self.reduce_pattern(subpattern),
                 mutability: *mutability,
             },
+            PatKind::Variant { adt_def, args, variant_index, subpatterns } => RPatKind::Variant {
+                adt_def: *adt_def,
+                args: self.instantiate(*args),
+                variant_index: *variant_index,
+                subpatterns: subpatterns
+                    .iter()
+                    .map(|FieldPat { field, pattern }| RFieldPat {
+                        field: *field,
+                        pattern: self.reduce_pattern(pattern),
+                    })
+                    .collect(),
+            },
+            PatKind::Leaf { subpatterns } => RPatKind::Leaf {
+                subpatterns: subpatterns
+                    .iter()
+                    .map(|FieldPat { field, pattern }| RFieldPat {
+                        field: *field,
+                        pattern: self.reduce_pattern(pattern),
+                    })
+                    .collect(),
+            },
+            PatKind::Constant { value } => RPatKind::Constant { value: *value },
             PatKind::Range(patrange) => RPatKind::Range(patrange.clone()),
             PatKind::Or { pats } => RPatKind::Or { pats: boxed_slice_to_new(pats) },
             _ => unimplemented!(),
@@ -96,7 +145,7 @@ impl<'tcx> ThirReducer<'tcx> {
     fn reduce_expr(&self, expr_id: &ExprId) -> Rc<RExpr<'tcx>> {
         let expr = &self.thir[*expr_id];
         let rexprkind = self.reduce_expr_kind(&expr.kind);
-        Rc::new(RExpr::new(rexprkind, expr.span))
+        Rc::new(RExpr::new(rexprkind, Some(self.instantiate(expr.ty)), expr.span))
     }
 
     fn reduce_expr_kind(&self, expr_kind: &ExprKind<'tcx>) -> RExprKind<'tcx> {
@@ -117,7 +166,7 @@ impl<'tcx> ThirReducer<'tcx> {
                 else_opt: unwrap_option(else_opt),
             },
             Call { ty, fun, args, from_hir_call, fn_span } => RExprKind::Call {
-                ty: *ty,
+                ty: self.instantiate(*ty),
                 fun: self.reduce_expr(fun),
                 args: args.iter().map(|arg| self.reduce_expr(arg)).collect(),
                 from_hir_call: *from_hir_call,
@@ -151,8 +200,8 @@ impl<'tcx> ThirReducer<'tcx> {
                 arms: arms
                     .iter()
                     .map(|arm| {
-                        let (arm, span) = self.handle_arm(arm);
-                        Rc::new(RExpr::new(arm, span))
+                        let (arm, ty, span) = self.handle_arm(arm);
+                        Rc::new(RExpr::new(arm, Some(ty), span))
                     })
                     .collect(),
             },
@@ -165,6 +214,22 @@ impl<'tcx> ThirReducer<'tcx> {
                 lhs: self.reduce_expr(lhs),
                 rhs: self.reduce_expr(rhs),
             },
+            Adt(adt_expr) => {
+                let AdtExpr { adt_def, variant_index, args, fields, base, .. } = &**adt_expr;
+                RExprKind::Adt {
+                    adt_def: *adt_def,
+                    variant_index: *variant_index,
+                    args: self.instantiate(*args),
+                    fields: fields
+                        .iter()
+                        .map(|FieldExpr { name, expr }| RFieldExpr {
+                            name: *name,
+                            expr: self.reduce_expr(expr),
+                        })
+                        .collect(),
+                    base: base.as_ref().map(|FruInfo { base, .. }| self.reduce_expr(base)),
+                }
+            }
             Field { lhs, variant_index, name } => RExprKind::Field {
                 lhs: self.reduce_expr(lhs),
                 variant_index: *variant_index,
@@ -194,6 +259,10 @@ impl<'tcx> ThirReducer<'tcx> {
             Tuple { fields } => {
                 RExprKind::Tuple { fields: fields.iter().map(|f| self.reduce_expr(f)).collect() }
             }
+            Closure(closure_expr) => RExprKind::Closure {
+                closure_id: closure_expr.closure_id,
+                upvars: closure_expr.upvars.iter().map(|upvar| self.reduce_expr(upvar)).collect(),
+            },
             PlaceTypeAscription { source, user_ty } => RExprKind::PlaceTypeAscription {
                 source: self.reduce_expr(source),
                 user_ty: user_ty.clone(),
@@ -207,12 +276,24 @@ impl<'tcx> ThirReducer<'tcx> {
                 RExprKind::NonHirLiteral { lit: *lit, user_ty: user_ty.clone() }
             }
             ZstLiteral { user_ty } => RExprKind::ZstLiteral { user_ty: user_ty.clone() },
-            NamedConst { def_id, args, user_ty } => {
-                RExprKind::NamedConst { def_id: *def_id, args: *args, user_ty: user_ty.clone() }
-            }
-            ConstParam { param, def_id } => {
-                RExprKind::ConstParam { param: *param, def_id: *def_id }
-            }
+            NamedConst { def_id, args, user_ty } => RExprKind::NamedConst {
+                def_id: *def_id,
+                args: self.instantiate(*args),
+                user_ty: user_ty.clone(),
+            },
+            ConstParam { param, def_id } => match self.instance {
+                // Known in an instantiation, so it is a literal there
+                Some((tcx, args)) => {
+                    match args
+                        .const_at(param.index as usize)
+                        .try_eval_scalar_int(tcx, ParamEnv::reveal_all())
+                    {
+                        Some((_, lit)) => RExprKind::NonHirLiteral { lit, user_ty: None },
+                        None => RExprKind::ConstParam { param: *param, def_id: *def_id },
+                    }
+                }
+                None => RExprKind::ConstParam { param: *param, def_id: *def_id },
+            },
             _ => unimplemented!(),
         }
     }
@@ -232,7 +313,7 @@ impl<'tcx> ThirReducer<'tcx> {
         self.reduce_expr_kind(&never_to_any.kind)
     }
 
-    fn handle_arm(&self, arm_id: &ArmId) -> (RExprKind<'tcx>, Span) {
+    fn handle_arm(&self, arm_id: &ArmId) -> (RExprKind<'tcx>, Ty<'tcx>, Span) {
         let arm = &self.thir.arms[*arm_id];
         (
             RExprKind::Arm {
@@ -244,6 +325,7 @@ impl<'tcx> ThirReducer<'tcx> {
                 },
                 body: self.reduce_expr(&arm.body),
             },
+            self.instantiate(self.thir[arm.body].ty),
             arm.span,
         )
     }
@@ -279,11 +361,12 @@ impl<'tcx> ThirReducer<'tcx> {
                         None
                     },
                     else_block: if let Some(block_id) = else_block {
-                        Some(Rc::new(RExpr::new(self.handle_block(&block_id), *span)))
+                        Some(Rc::new(RExpr::new(self.handle_block(&block_id), None, *span)))
                     } else {
                         None
                     },
                 },
+                None,
                 *span,
             )),
         }
@@ -1,4 +1,5 @@
 // rustc crates
+use rustc_hir::def::DefKind;
 use rustc_middle::ty::TyCtxt;
 use rustc_span::def_id::LocalDefId;
 
@@ -11,7 +12,10 @@ use crate::thir::{rthir::*, *};
 
 pub fn get_fn_map<'tcx>(tcx: &TyCtxt<'tcx>) -> Map<LocalDefId, Rc<RThir<'tcx>>> {
     let mut map: Map<LocalDefId, Rc<RThir<'tcx>>> = Map::new();
-    tcx.mir_keys(()).iter().for_each(|&key| {
+    // Ctors of tuple structs and variants have MIR but no THIR body
+    let keys =
+        tcx.mir_keys(()).iter().filter(|&&key| !matches!(tcx.def_kind(key), DefKind::Ctor(..)));
+    keys.for_each(|&key| {
         let rthir = generate_rthir(&tcx, key).expect("Generate ReducedTHIR failed");
         // println!("{:?}, {:?}", key, rthir);
         map.insert(key, Rc::new(rthir));
@@ -4,3 +4,5 @@ pub fn invariant(_: bool) {}
 pub fn rand_bool() -> bool { false }
 pub fn rand_int<T: From<i8>>() -> T { T::from(0) }
 pub fn rand_float<T: From<f32>>() -> T { T::from(0.0) }
+pub fn requires(_: bool) {}
+pub fn ensures<T>(_: impl Fn(T) -> bool) {}
@@ -0,0 +1,13 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn main() {
+    let n = rand_int::<i32>();
+    t3assume(0 <= n && n <= 10);
+
+    let mut i = 0;
+    while i < n {
+        i += 1;
+    }
+    t3assert(i == n);
+}
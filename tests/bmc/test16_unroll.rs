@@ -0,0 +1,20 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn main() {
+    let n = rand_int::<i32>();
+    t3assume(0 <= n);
+    t3assume(n <= 3);
+
+    let mut i = 0;
+    let mut sum = 0;
+    while i < n {
+        i += 1;
+        if i == 2 {
+            continue;
+        }
+        sum += i;
+    }
+    t3assert(i == n);
+    t3assert(sum <= 4);
+}
@@ -0,0 +1,8 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn main() {
+    let a = rand_int::<i32>();
+    t3assume(0 <= a && a < 10);
+    assert_eq!(a * 2, a + 2);
+}
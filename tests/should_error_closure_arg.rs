@@ -0,0 +1,12 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn apply<F: Fn(i32) -> i32>(f: F, x: i32) -> i32 {
+    f(x)
+}
+
+fn main() {
+    let inc = |x| x + 1;
+    let y = apply(inc, 3);
+    t3assert(y == 4);
+}
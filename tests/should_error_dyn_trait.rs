@@ -0,0 +1,26 @@
+extern crate t3modules;
+use t3modules::*;
+
+trait Area {
+    fn area(&self) -> i32;
+}
+
+struct Square {
+    side: i32,
+}
+
+impl Area for Square {
+    fn area(&self) -> i32 {
+        self.side * self.side
+    }
+}
+
+fn total(s: &dyn Area) -> i32 {
+    s.area()
+}
+
+fn main() {
+    let sq = Square { side: 3 };
+    let t = total(&sq);
+    t3assert(t == 9);
+}
@@ -0,0 +1,12 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn bump(mut n: i32) -> i32 {
+    ensures(|r: i32| r == n);
+    n += 1;
+    n
+}
+
+fn main() {
+    t3assert(bump(5) == 5);
+}
@@ -0,0 +1,9 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn main() {
+    let a: f64 = rand_float();
+    t3assume(a > 1.0);
+    let r = a % 1.0;
+    t3assert(r < 1.0);
+}
@@ -0,0 +1,10 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn main() {
+    let mut s = 0;
+    for i in 0..10 {
+        s += i;
+    }
+    t3assert(s >= 0);
+}
@@ -0,0 +1,10 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn main() {
+    let i = rand_int::<i32>();
+    t3assume(0 <= i && i <= 4);
+    let i: usize = if i < 4 { 3 } else { 4 };
+    let xs = [1, 2, 3, 4];
+    t3assert(xs[i] > 0);
+}
@@ -0,0 +1,12 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn main() {
+    let mut x = 0;
+    let mut c = move || {
+        x += 1;
+        x
+    };
+    c();
+    t3assert(x == 1);
+}
@@ -0,0 +1,12 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn bump(p: (&mut i32, i32)) {
+    *p.0 += p.1;
+}
+
+fn main() {
+    let mut x = 0;
+    bump((&mut x, 1));
+    t3assert(x == 0);
+}
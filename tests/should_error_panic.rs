@@ -0,0 +1,10 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn main() {
+    let a = rand_int::<i32>();
+    t3assume(0 <= a && a < 10);
+    if a > 8 {
+        panic!("out of range");
+    }
+}
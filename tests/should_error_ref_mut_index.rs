@@ -0,0 +1,11 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn main() {
+    let mut xs = [0, 0];
+    let mut i: usize = 0;
+    let ref mut r = xs[i];
+    i += 1;
+    *r = 5;
+    t3assert(xs[1] == 5);
+}
@@ -0,0 +1,14 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn main() {
+    let mut x = 0;
+    let ref mut r = x;
+    let mut i = 0;
+    while i < 5 {
+        invariant(i <= 5);
+        *r += 1;
+        i += 1;
+    }
+    t3assert(x <= 1);
+}
@@ -0,0 +1,9 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn main() {
+    let a = rand_int::<i32>();
+    let o = if a > 10 { Some(a) } else { None };
+    let v = o.unwrap();
+    t3assert(v > 10);
+}
@@ -0,0 +1,44 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn count_up(m: i32) {
+    let mut c = 0;
+    invariant(c <= m);
+    while c < m {
+        c += 1;
+    }
+    t3assert(c == m);
+}
+
+fn main() {
+    let n = rand_int::<i32>();
+    t3assume(0 <= n);
+
+    let mut i = 0;
+    let mut total = 0;
+    invariant(i <= n);
+    invariant(0 <= total);
+    while i < n {
+        let mut j = 0;
+        invariant(j <= n);
+        invariant(0 <= total);
+        while j < n {
+            j += 1;
+            total += 1;
+        }
+        i += 1;
+    }
+    t3assert(i == n);
+    t3assert(0 <= total);
+
+    if 0 < n {
+        let mut k = n;
+        invariant(0 <= k);
+        while 0 < k {
+            k -= 1;
+        }
+        t3assert(k == 0);
+    }
+
+    count_up(n);
+}
@@ -0,0 +1,29 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn main() {
+    let n = rand_int::<i32>();
+    t3assume(0 <= n);
+
+    let mut i = 0;
+    while i < n {
+        invariant(i <= n);
+        i += 1;
+    }
+    t3assert(i == n);
+
+    let mut j = 0;
+    let mut sum = 0;
+    loop {
+        invariant(0 <= j);
+        invariant(j <= n);
+        invariant(0 <= sum);
+        if j >= n {
+            break;
+        }
+        sum += j;
+        j += 1;
+    }
+    t3assert(j == n);
+    t3assert(0 <= sum);
+}
@@ -0,0 +1,21 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn main() {
+    let mut x = rand_int::<i32>();
+    let mut y = rand_int::<i32>();
+    t3assume(0 < x);
+    t3assume(x == y);
+
+    let mut i = 0;
+    while i < x {
+        i += 1;
+    }
+    t3assert(i == y);
+
+    let mut j = x;
+    while 0 < j {
+        j -= 1;
+    }
+    t3assert(j == 0);
+}
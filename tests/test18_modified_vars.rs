@@ -0,0 +1,65 @@
+extern crate t3modules;
+use t3modules::*;
+
+struct Counter {
+    total: i32,
+}
+
+fn inc(x: &mut i32) { *x += 1; }
+
+fn main() {
+    let n = rand_int::<i32>();
+    t3assume(0 <= n);
+
+    let mut i = 0;
+    let mut k = 0;
+    invariant(i <= n);
+    invariant(k <= i);
+    while i < n {
+        {
+            if k < i {
+                k += 1;
+            }
+        }
+        i += 1;
+    }
+
+    t3assert(i == n);
+    t3assert(k <= n);
+
+    let mut j = 0;
+    let mut s = 0;
+    let mut c = Counter { total: 0 };
+    let mut a = [0, 0];
+    let mut m = 0;
+    while j < n {
+        invariant(j <= n);
+        invariant(s == j);
+        invariant(c.total == 2 * j);
+        invariant(a[1] == j);
+        invariant(0 <= m && m <= j);
+        inc(&mut s);
+        c.total += 2;
+        a[1] += 1;
+        match j % 2 {
+            0 => m += 1,
+            _ => (),
+        }
+        j += 1;
+    }
+
+    t3assert(s == n);
+    t3assert(c.total == 2 * n);
+    t3assert(a[1] == n);
+    t3assert(m <= n);
+
+    let mut x = 0;
+    let ref mut r = x;
+    let mut k = 0;
+    while k < 5 {
+        invariant(k <= 5 && *r == k);
+        *r += 1;
+        k += 1;
+    }
+    t3assert(x == 5);
+}
@@ -0,0 +1,40 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn min_max(n: i32, m: i32) -> (i32, i32) {
+    if n < m {
+        (n, m)
+    } else {
+        (m, n)
+    }
+}
+
+fn sum_pair((x, y): (i32, i32)) -> i32 { x + y }
+
+fn main() {
+    let a = rand_int::<i32>();
+    t3assume(0 <= a && a < 100);
+
+    let (q, r) = min_max(a, 7);
+    t3assert(q <= r);
+    t3assert(q == a || r == a);
+
+    let mut t = (a, true);
+    t.0 = t.0 + 1;
+    t3assert(t.0 == a + 1);
+    t3assert(t.1);
+
+    let s = sum_pair((q, r));
+    t3assert(s == q + r);
+
+    let sign = match (a, t.1) {
+        (0, _) => 0,
+        (k, true) if k < 50 => 1,
+        _ => 2,
+    };
+    t3assert(sign <= 2);
+    match (q, r) {
+        (7, x) => t3assert(x == a),
+        _ => t3assert(a < 7),
+    }
+}
@@ -0,0 +1,47 @@
+extern crate t3modules;
+use t3modules::*;
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+struct Segment {
+    from: Point,
+    to: Point,
+}
+
+fn origin() -> Point {
+    Point { x: 0, y: 0 }
+}
+
+fn manhattan(pt: Point) -> i32 {
+    let Point { x: px, y: py } = pt;
+    let ax = if px < 0 { -px } else { px };
+    let ay = if py < 0 { -py } else { py };
+    ax + ay
+}
+
+fn main() {
+    let a = rand_int::<i32>();
+    t3assume(0 <= a && a < 100);
+
+    let mut p = origin();
+    p.x += a;
+    t3assert(p.x == a && p.y == 0);
+
+    let q = Point { y: 3, ..p };
+    t3assert(q.x == a && q.y == 3);
+
+    let d = manhattan(q);
+    t3assert(d == a + 3);
+
+    let mut s = Segment { from: origin(), to: Point { x: a, y: a } };
+    s.to.y = 1;
+    t3assert(s.to.x == a && s.to.y == 1 && s.from.x == 0);
+
+    match s.from {
+        Point { x: 0, y } => t3assert(y == 0),
+        _ => t3assert(false),
+    }
+}
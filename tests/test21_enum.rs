@@ -0,0 +1,91 @@
+extern crate t3modules;
+use t3modules::*;
+
+enum Shape {
+    Empty,
+    Square(i32),
+    Rect { w: i32, h: i32 },
+}
+
+fn area(shape: Shape) -> i32 {
+    match shape {
+        Shape::Empty => 0,
+        Shape::Square(side) => side * side,
+        Shape::Rect { w, h } => w * h,
+    }
+}
+
+fn side(shape: &Shape) -> i32 {
+    match shape {
+        Shape::Empty => 0,
+        &Shape::Square(side) => side,
+        Shape::Rect { w, .. } => *w,
+    }
+}
+
+fn checked_sub(lhs: i32, rhs: i32) -> Option<i32> {
+    if lhs >= rhs {
+        Some(lhs - rhs)
+    } else {
+        None
+    }
+}
+
+fn parse_digit(code: i32) -> Result<i32, bool> {
+    if 48 <= code && code < 58 {
+        Ok(code - 48)
+    } else {
+        Err(code < 48)
+    }
+}
+
+fn main() {
+    let a = rand_int::<i32>();
+    t3assume(0 <= a && a < 100);
+
+    let sh = if a < 30 {
+        Shape::Empty
+    } else if a < 60 {
+        Shape::Square(a)
+    } else {
+        Shape::Rect { w: a, h: 2 }
+    };
+    let s = area(sh);
+    t3assert(a < 30 && s == 0 || 30 <= a && a < 60 && s == a * a || 60 <= a && s == 2 * a);
+
+    let d = checked_sub(a, 10);
+    if let Some(v) = d {
+        t3assert(a >= 10 && v == a - 10);
+    } else {
+        t3assert(a < 10);
+    }
+
+    let n = match parse_digit(a) {
+        Ok(digit) => digit,
+        Err(true) => -1,
+        Err(false) => 10,
+    };
+    t3assert(-1 <= n && n <= 10);
+
+    let mut o = None;
+    if a > 50 {
+        o = Some(a);
+    }
+    match o {
+        Some(m) if m > 50 => t3assert(a > 50),
+        Some(_) => t3assert(false),
+        None => t3assert(a <= 50),
+    }
+
+    match &o {
+        Some(m) => t3assert(*m > 50),
+        None => t3assert(a <= 50),
+    }
+    if let Some(m) = &o {
+        t3assert(*m == a);
+    }
+    let sq = Shape::Square(a);
+    t3assert(side(&sq) == a);
+    let r = Shape::Rect { w: 3, h: a };
+    t3assert(side(&r) == 3);
+}
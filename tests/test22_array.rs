@@ -0,0 +1,37 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn first_or_last(arr: [i32; 4], last: bool) -> i32 {
+    if last {
+        arr[3]
+    } else {
+        arr[0]
+    }
+}
+
+fn main() {
+    let a = rand_int::<i32>();
+    t3assume(0 <= a && a < 100);
+    let i: usize = if a < 40 { 1 } else { 3 };
+
+    let mut xs = [a, a + 1, a + 2, a + 3];
+    t3assert(xs[i] == a + 1 || xs[i] == a + 3);
+    xs[i] = 0;
+    t3assert(xs[i] == 0);
+    xs[0] += 5;
+    t3assert(xs[0] == a + 5);
+
+    let mut zs = [0; 8];
+    zs[2 * i + 1] = a;
+    t3assert(zs[2 * i] == 0 && zs[2 * i + 1] == a);
+
+    let pairs = [(1, true), (2, false)];
+    t3assert(pairs[1].0 == 2 && !pairs[1].1);
+
+    let empty: [i32; 0] = [];
+    let k = if a < 0 { empty[0] } else { a };
+    t3assert(k == a);
+
+    let e = first_or_last(xs, a > 50);
+    t3assert(a > 50 && e == xs[3] || a <= 50 && e == xs[0]);
+}
@@ -0,0 +1,67 @@
+extern crate t3modules;
+use t3modules::*;
+
+struct Counter {
+    hits: i32,
+    misses: i32,
+}
+
+fn larger(lhs: &i32, rhs: &i32) -> i32 {
+    if *lhs > *rhs {
+        *lhs
+    } else {
+        *rhs
+    }
+}
+
+fn bump(r: &mut i32) {
+    *r += 1;
+}
+
+fn reset_if_negative(v: &mut i32) {
+    if *v < 0 {
+        *v = 0;
+    }
+}
+
+fn record(counter: &mut Counter, hit: bool) {
+    if hit {
+        counter.hits += 1;
+    } else {
+        counter.misses += 1;
+    }
+}
+
+fn add_one(q: &mut i32) {
+    *q = *q + 1;
+}
+
+fn twice(t: &mut i32) {
+    add_one(t);
+    *t = *t * 2;
+}
+
+fn main() {
+    let a = rand_int::<i32>();
+    let b = rand_int::<i32>();
+    t3assume(-100 < a && a < 100 && -100 < b && b < 100);
+
+    let m = larger(&a, &b);
+    t3assert(m >= a && m >= b);
+
+    let mut x = a;
+    bump(&mut x);
+    t3assert(x == a + 1);
+
+    let mut y = b;
+    reset_if_negative(&mut y);
+    t3assert(y >= 0 && (b < 0 || y == b));
+
+    let mut c = Counter { hits: 0, misses: 0 };
+    record(&mut c, a > 0);
+    t3assert(c.hits + c.misses == 1);
+
+    let mut p = (a, b);
+    twice(&mut p.0);
+    t3assert(p.0 == 2 * a + 2 && p.1 == b);
+}
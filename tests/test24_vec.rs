@@ -0,0 +1,42 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn total(s: &[i32]) -> i32 {
+    s.iter().sum()
+}
+
+fn last_or_zero(w: &mut Vec<i32>) -> i32 {
+    match w.pop() {
+        Some(top) => top,
+        None => 0,
+    }
+}
+
+fn main() {
+    let a = rand_int::<i32>();
+    t3assume(0 <= a && a < 100);
+
+    let mut v: Vec<i32> = Vec::new();
+    t3assert(v.is_empty());
+    v.push(a);
+    v.push(2);
+    v.push(3);
+    t3assert(v.len() == 3 && !v.is_empty());
+    t3assert(v[0] == a && v[2] == 3);
+
+    v[1] = a + 1;
+    v.swap(0, 2);
+    t3assert(v[0] == 3 && v[1] == a + 1 && v[2] == a);
+
+    let t = total(&v[1..]);
+    t3assert(t == 2 * a + 1);
+    let u = &v[..2];
+    t3assert(u.len() == 2 && u[1] == a + 1);
+
+    let top = last_or_zero(&mut v);
+    t3assert(top == a && v.len() == 2);
+    match v.pop() {
+        Some(second) => t3assert(second == a + 1 && v.len() == 1),
+        None => t3assert(false),
+    }
+}
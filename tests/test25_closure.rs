@@ -0,0 +1,39 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn main() {
+    let a = rand_int::<i32>();
+    t3assume(-100 < a && a < 100);
+
+    let offset = 10;
+    let shift = |x: i32| x + offset;
+    let s = shift(a);
+    t3assert(s == a + 10);
+
+    let mut count = 0;
+    let mut tick = || count += 1;
+    tick();
+    tick();
+    t3assert(count == 2);
+
+    let clamp = |y: i32| {
+        if y < 0 {
+            0
+        } else {
+            y
+        }
+    };
+    let c = clamp(a);
+    t3assert(c >= 0 && c >= a);
+
+    let mut total = a;
+    let mut add = |z: i32| {
+        total += z;
+    };
+    add(5);
+    t3assert(total == a + 5);
+
+    let base = a;
+    let plus = move |w: i32| base + w;
+    t3assert(plus(1) == a + 1);
+}
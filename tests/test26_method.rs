@@ -0,0 +1,35 @@
+extern crate t3modules;
+use t3modules::*;
+
+struct Account {
+    balance: i32,
+    limit: i32,
+}
+
+impl Account {
+    fn new(limit: i32) -> Account { Account { balance: 0, limit } }
+
+    fn is_empty(&self) -> bool { self.balance == 0 }
+
+    fn available(&self) -> i32 { self.limit - self.balance }
+
+    fn charge(&mut self, amount: i32) {
+        if amount <= self.available() {
+            self.balance += amount;
+        }
+    }
+
+    fn into_balance(self) -> i32 { self.balance }
+}
+
+fn main() {
+    let l = rand_int::<i32>();
+    t3assume(0 < l && l < 1000);
+
+    let mut acc = Account::new(l);
+    t3assert(acc.is_empty() && acc.limit == l);
+    acc.charge(l);
+    t3assert(acc.balance == l);
+    let b = acc.into_balance();
+    t3assert(b == l);
+}
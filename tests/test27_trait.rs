@@ -0,0 +1,39 @@
+extern crate t3modules;
+use t3modules::*;
+
+trait Shape {
+    fn area(&self) -> i32;
+    fn perimeter(&self) -> i32;
+}
+
+struct Rect {
+    w: i32,
+    h: i32,
+}
+
+struct Square {
+    side: i32,
+}
+
+impl Shape for Rect {
+    fn area(&self) -> i32 { self.w * self.h }
+
+    fn perimeter(&self) -> i32 { 2 * (self.w + self.h) }
+}
+
+impl Shape for Square {
+    fn area(&self) -> i32 { self.side * self.side }
+
+    fn perimeter(&self) -> i32 { 4 * self.side }
+}
+
+fn main() {
+    let n = rand_int::<i32>();
+    t3assume(0 < n && n < 10);
+
+    let r = Rect { w: n, h: 20 };
+    t3assert(r.area() == 20 * n);
+
+    let s = Square { side: n };
+    t3assert(s.perimeter() == 4 * n);
+}
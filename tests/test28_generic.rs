@@ -0,0 +1,53 @@
+extern crate t3modules;
+use std::ops::Add;
+use t3modules::*;
+
+trait Shape {
+    fn area(&self) -> i32;
+
+    fn is_large(&self) -> bool { self.area() > 100 }
+}
+
+struct Rect {
+    w: i32,
+    h: i32,
+}
+
+impl Shape for Rect {
+    fn area(&self) -> i32 { self.w * self.h }
+}
+
+struct Pair<T> {
+    first: T,
+    second: T,
+}
+
+fn larger<T: PartialOrd + Copy>(a: T, b: T) -> T {
+    if a > b {
+        a
+    } else {
+        b
+    }
+}
+
+fn sum3<T: Add<Output = T> + Copy>(x: T, y: T, z: T) -> T { x + y + z }
+
+fn swapped<T: Copy>(p: Pair<T>) -> Pair<T> { Pair { first: p.second, second: p.first } }
+
+fn main() {
+    let n = rand_int::<i32>();
+    let m = rand_int::<i32>();
+    t3assume(0 < n && n < 10 && -10 < m && m < 10);
+
+    let g = larger(n, m);
+    t3assert(g >= n && g >= m);
+
+    let s = sum3(n, m, 1);
+    t3assert(s == n + m + 1);
+
+    let q = swapped(Pair { first: n, second: m });
+    t3assert(q.first == m && q.second == n);
+
+    let r = Rect { w: n, h: 20 };
+    t3assert(r.is_large() == (n > 5));
+}
@@ -0,0 +1,47 @@
+extern crate t3modules;
+use t3modules::*;
+
+const LIMIT: i32 = 100;
+
+struct Buffer {
+    used: usize,
+}
+
+impl Buffer {
+    const CAPACITY: usize = 8;
+}
+
+trait Scale {
+    const TOP: i32;
+}
+
+struct Percent {
+    value: i32,
+}
+
+impl Scale for Percent {
+    const TOP: i32 = 100;
+}
+
+fn first_or<const N: usize>(a: [i32; N], d: i32) -> i32 {
+    if N > 0 {
+        a[0]
+    } else {
+        d
+    }
+}
+
+fn main() {
+    let x = rand_int::<i32>();
+    t3assume(0 <= x && x < LIMIT);
+    t3assert(x < i32::MAX && i32::MIN < -x && x + LIMIT < 200);
+
+    let b = Buffer { used: Buffer::CAPACITY - 1 };
+    t3assert(b.used < Buffer::CAPACITY);
+
+    let p = Percent { value: x };
+    t3assert(p.value <= Percent::TOP);
+
+    let f = first_or([x, 1, 2], 0);
+    t3assert(f == x);
+}
@@ -0,0 +1,43 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn first((p, _): (i32, i32), _: bool) -> i32 { p }
+
+fn double(ref r: i32) -> i32 { *r * 2 }
+
+fn main() {
+    let x: i32 = rand_int();
+    let y: i32 = rand_int();
+    t3assume(-100 < x && x < 100 && -100 < y && y < 100);
+
+    let _ = x + y;
+    let _: i32 = y;
+    let z: (i32, i32) = (x, y);
+    t3assert(z.0 == x);
+
+    let whole @ (a, b) = (x, y);
+    t3assert(whole.0 == a && whole.1 == b && a == x);
+
+    let ref shared = x;
+    t3assert(*shared == x);
+
+    let mut w = y;
+    let ref mut target = w;
+    *target += 1;
+    t3assert(w == y + 1);
+
+    let mut pair = (x, y);
+    let (ref mut left, _) = pair;
+    *left = 7;
+    t3assert(pair.0 == 7 && pair.1 == y);
+
+    let mut xs = [x, y];
+    let ref mut second = xs[1];
+    *second = 5;
+    t3assert(xs[0] == x && xs[1] == 5);
+
+    let f = first((x, y), true);
+    t3assert(f == x);
+    let d = double(y);
+    t3assert(d == 2 * y);
+}
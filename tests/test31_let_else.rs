@@ -0,0 +1,28 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn main() {
+    let n = rand_int::<i32>();
+    let opt = if n > 0 { Some(n) } else { None };
+    let Some(v) = opt else {
+        return;
+    };
+    t3assert(v > 0 && v == n);
+
+    let 1..=100 = v else {
+        return;
+    };
+    t3assert(v <= 100);
+
+    let mut i = 0;
+    let mut evens = 0;
+    while i < 10 {
+        invariant(0 <= i && i <= 10 && 0 <= evens && evens <= i);
+        i += 1;
+        let 0 = i % 2 else {
+            continue;
+        };
+        evens += 1;
+    }
+    t3assert(evens <= 10);
+}
@@ -0,0 +1,74 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn abs(n: i32) -> i32 {
+    if n < 0 {
+        return -n;
+    }
+    n
+}
+
+fn sign(n: i32) -> i32 {
+    match n {
+        0 => return 0,
+        _ if n < 0 => return -1,
+        _ => (),
+    }
+    1
+}
+
+fn first_multiple(n: i32, k: i32) -> i32 {
+    let mut i = 1;
+    while i <= 10 {
+        invariant(1 <= i && i <= 11);
+        if (n * i) % k == 0 {
+            return i;
+        }
+        i += 1;
+    }
+    0
+}
+
+fn first_over(limit: i32) -> i32 {
+    let mut i = 0;
+    loop {
+        invariant(i >= 0);
+        if i > limit {
+            return i;
+        }
+        i += 1;
+    }
+}
+
+fn clamp(x: &mut i32, max: i32) {
+    if *x <= max {
+        return;
+    }
+    *x = max;
+}
+
+fn main() {
+    let n = rand_int::<i32>();
+    if n > -1000 && n < 1000 {
+        let a = abs(n);
+        t3assert(a >= 0);
+        t3assert(a == n || a == -n);
+    }
+
+    let s = sign(n);
+    t3assert(s == 0 || s == 1 || s == -1);
+    t3assert(n >= 0 || s == -1);
+
+    let i = first_multiple(n, 2);
+    t3assert(0 <= i && i <= 10);
+
+    let mut x = n;
+    clamp(&mut x, 100);
+    t3assert(x <= 100);
+    t3assert(x == n || n > 100);
+
+    if n > 0 && n < 100 {
+        let r = first_over(n);
+        t3assert(r > n);
+    }
+}
@@ -0,0 +1,39 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn inc(x: i32) -> i32 {
+    t3assert(x > 0);
+    x + 1
+}
+
+fn double(y: i32) -> i32 { y * 2 }
+
+fn succ(x: i32) -> i32 {
+    t3assert(x > 0);
+    x + 1
+}
+
+fn twice(y: i32) -> i32 { y * 2 }
+
+fn bump(c: &mut i32) -> i32 {
+    *c += 1;
+    *c
+}
+
+fn origin() -> i32 { 0 }
+
+fn main() {
+    let a = 5;
+    let s = inc(a) + double(a);
+    t3assert(s == 16);
+
+    let mut c = 0;
+    if bump(&mut c) > 0 {
+        t3assert(c == 1);
+    }
+
+    let t = twice(succ(a));
+    t3assert(t == 12);
+
+    t3assert(origin() == origin());
+}
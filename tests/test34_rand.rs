@@ -0,0 +1,37 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn clamp(x: i32) -> i32 {
+    if x < 0 {
+        0
+    } else {
+        x
+    }
+}
+
+fn main() {
+    let mut x = 0;
+    if rand_bool() {
+        x = rand_int();
+        t3assume(x > 0);
+    }
+    t3assert(x >= 0);
+
+    let c = clamp(rand_int());
+    t3assert(c >= 0);
+
+    let w = rand_int::<i64>() * 2;
+    t3assert(w % 2 == 0);
+
+    let mut sum = 0;
+    let mut i = 0;
+    while i < 3 {
+        invariant(0 <= i && i <= 3 && 0 <= sum && sum <= 10 * i);
+        let d: i32 = rand_int();
+        if 0 <= d && d <= 10 {
+            sum += d;
+        }
+        i += 1;
+    }
+    t3assert(sum <= 30);
+}
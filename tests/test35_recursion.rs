@@ -0,0 +1,84 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn sum_to(n: i32) -> i32 {
+    requires(n >= 0);
+    ensures(|s: i32| s >= n);
+    if n == 0 {
+        return 0;
+    }
+    n + sum_to(n - 1)
+}
+
+fn is_even(n: i32) -> bool {
+    requires(n >= 0);
+    ensures(|even: bool| even == (n % 2 == 0));
+    if n == 0 {
+        true
+    } else {
+        is_odd(n - 1)
+    }
+}
+
+fn is_odd(n: i32) -> bool {
+    requires(n >= 0);
+    ensures(|odd: bool| odd == (n % 2 == 1));
+    if n == 0 {
+        false
+    } else {
+        is_even(n - 1)
+    }
+}
+
+fn bump(mut n: i32) -> i32 {
+    ensures(|r: i32| r == n + 1);
+    n += 1;
+    n
+}
+
+trait Count {
+    fn count(&self, n: i32) -> i32;
+}
+
+struct Steps;
+
+impl Count for Steps {
+    fn count(&self, n: i32) -> i32 {
+        if n <= 0 {
+            0
+        } else {
+            1 + count_with(self, n - 1)
+        }
+    }
+}
+
+fn count_with<T: Count>(t: &T, n: i32) -> i32 {
+    t.count(n)
+}
+
+fn fact(n: i32) -> i32 {
+    if n <= 1 {
+        1
+    } else {
+        n * fact(n - 1)
+    }
+}
+
+fn fib(n: i32) -> i32 {
+    if n <= 1 {
+        n
+    } else {
+        fib(n - 1) + fib(n - 2)
+    }
+}
+
+fn main() {
+    let n = rand_int::<i32>();
+    t3assume(n >= 0 && n < 1000);
+    t3assert(sum_to(n) >= n);
+    t3assert(is_even(n) || n % 2 == 1);
+    t3assert(bump(5) == 6);
+    t3assert(count_with(&Steps, n) >= 0);
+    t3assert(fact(3) == 6);
+    t3assert(fib(3) == 2);
+}
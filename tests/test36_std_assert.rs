@@ -0,0 +1,34 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn check_nonzero(a: i32) {
+    debug_assert!(a != 0);
+    assert!(a != 0);
+}
+
+fn unwrap_both(a: i32) -> i32 {
+    let o = if a > 10 { Some(a) } else { None };
+    if a > 10 {
+        let v = o.unwrap();
+        let w = o.expect("checked above");
+        v + w
+    } else {
+        0
+    }
+}
+
+fn main() {
+    let a = rand_int::<i32>();
+    t3assume(a > 0 && a < 100);
+    assert!(a > 0);
+    assert!(a < 100, "a is {}", a);
+    assert_eq!(a + 1, 1 + a);
+    check_nonzero(a);
+    if a > 200 {
+        panic!("out of range");
+    }
+    t3assert(unwrap_both(a) >= 0);
+    if a > 500 {
+        unreachable!();
+    }
+}
@@ -0,0 +1,26 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn inc_twice(mut n: i32) -> i32 {
+    n += 1;
+    let n = n + 1;
+    n
+}
+
+fn chain(n: i32) -> i32 {
+    let m = inc_twice(n);
+    let m = inc_twice(m);
+    m
+}
+
+fn main() {
+    let a = rand_int::<i32>();
+    t3assume(a > 0 && a < 100);
+    let a = a + 1;
+    t3assert(inc_twice(a) == a + 2);
+    t3assert(inc_twice(a) + inc_twice(a) == 2 * a + 4);
+    t3assert(chain(a) == a + 4);
+    assert_eq!(a, a);
+    assert_eq!(a + 1, 1 + a);
+    assert_ne!(a, 0);
+}
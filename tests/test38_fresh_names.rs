@@ -0,0 +1,33 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn bump(x: &mut i32) {
+    *x += 1;
+}
+
+fn main() {
+    let a = rand_int::<i32>();
+    let mut x = 0;
+    if a > 0 {
+        x = 1;
+    }
+    if a > 5 {
+        x = 2;
+    }
+    t3assert(a <= 5 || x == 2);
+    let mut y = 0;
+    if a > 1 {
+        bump(&mut y);
+    } else {
+        bump(&mut y);
+        bump(&mut y);
+    }
+    t3assert(y == 1 || a <= 1);
+    let mut f = || x += 1;
+    if a > 2 {
+        f();
+    } else {
+        f();
+    }
+    t3assert(a <= 5 || x == 3);
+}
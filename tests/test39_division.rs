@@ -0,0 +1,18 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn main() {
+    let a = rand_int::<i32>();
+    t3assume(a > -100 && a < 100);
+    t3assert((a / 3) * 3 + a % 3 == a);
+    t3assert(-a / 3 == -(a / 3));
+    t3assert(a / -3 == -(a / 3));
+    let n = -7;
+    t3assert(n / 2 == -3);
+    t3assert(7 / -2 == -3);
+    t3assert(n / -2 == 3);
+    t3assert(n % 2 == -1);
+    let mut q = n;
+    q /= 2;
+    t3assert(q == -3);
+}
@@ -0,0 +1,52 @@
+extern crate t3modules;
+use t3modules::*;
+
+struct Cell {
+    v: i32,
+}
+
+impl Cell {
+    fn set(&mut self, v: i32) -> i32 {
+        self.v = v;
+        v
+    }
+}
+
+fn bump(x: &mut i32) -> i32 {
+    *x += 1;
+    *x
+}
+
+fn id(x: i32) -> i32 {
+    x
+}
+
+fn positive(x: i32) -> i32 {
+    requires(x > 0);
+    ensures(|r: i32| r == x);
+    x
+}
+
+fn add(x: i32, y: i32) -> i32 {
+    x + y
+}
+
+fn main() {
+    let mut a = 0;
+    let r = id(bump(&mut a));
+    t3assert(a == 1);
+    t3assert(r == 1);
+    let s = add(bump(&mut a), bump(&mut a));
+    t3assert(a == 3);
+    t3assert(s == 5);
+    let mut c = Cell { v: 0 };
+    let mut d = Cell { v: 0 };
+    d.set(c.set(9));
+    t3assert(c.v == 9);
+    t3assert(d.v == 9);
+    let f = |x: i32| x * 2;
+    t3assert(f(bump(&mut a)) == 8);
+    t3assert(a == 4);
+    t3assert(positive(bump(&mut a)) == 5);
+    t3assert(a == 5);
+}
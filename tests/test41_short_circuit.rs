@@ -0,0 +1,29 @@
+extern crate t3modules;
+use t3modules::*;
+
+fn pos(a: i32) -> bool {
+    t3assert(a > 0);
+    a > 5
+}
+
+fn main() {
+    let a = rand_int::<i32>();
+    let mut v = Vec::new();
+    if a > 0 {
+        v.push(a);
+    }
+    if 0 < v.len() && v[0] > 0 {
+        t3assert(a > 0);
+    }
+    t3assert(v.is_empty() || v[0] == a);
+    let o = if a > 10 { Some(a) } else { None };
+    if o.is_some() && o.unwrap() > 0 {
+        t3assert(a > 10);
+    }
+    t3assert(o.is_none() || o.unwrap() > 10);
+    if a > 0 && pos(a) {
+        t3assert(a > 5);
+    }
+    let ok = a <= 0 || pos(a);
+    t3assert(ok == (a <= 0 || a > 5));
+}